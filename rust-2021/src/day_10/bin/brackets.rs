@@ -0,0 +1,136 @@
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+/// The bracket pairs a line may use, as (open, close).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Brackets {
+    pairs: Vec<(char, char)>
+}
+
+impl Default for Brackets {
+    fn default() -> Self {
+        Brackets { pairs: vec![('(', ')'), ('[', ']'), ('{', '}'), ('<', '>')] }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Status {
+    /// The first closer that doesn't match. `expected` is `None` when
+    /// nothing was open, and `column` counts from 1.
+    Corrupted { expected: Option<char>, found: char, column: usize },
+    /// Every bracket matched but some are still open; `completion` closes
+    /// them, innermost first.
+    Incomplete { completion: String },
+    Balanced
+}
+
+impl Display for Status {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Status::Corrupted { expected: Some(e), found, column } =>
+                write!(f, "corrupted at column {}: expected {:?} but found {:?}", column, e, found),
+            Status::Corrupted { expected: None, found, column } =>
+                write!(f, "corrupted at column {}: found {:?} with nothing open", column, found),
+            Status::Incomplete { completion } => write!(f, "incomplete: complete with {}", completion),
+            Status::Balanced => write!(f, "balanced")
+        }
+    }
+}
+
+impl Brackets {
+    /// Fails if a character is used twice, even as both halves of one pair,
+    /// since then there'd be no telling an opener from a closer.
+    pub fn new(pairs: &[(char, char)]) -> Result<Self, String> {
+        let mut seen = vec![];
+        for (open, close) in pairs {
+            for c in [open, close] {
+                if seen.contains(c) {
+                    return Err(format!("{:?} is used by more than one bracket", c));
+                }
+                seen.push(*c);
+            }
+        }
+
+        Ok(Brackets { pairs: pairs.to_vec() })
+    }
+
+    pub fn pairs(&self) -> &[(char, char)] {
+        &self.pairs
+    }
+
+    fn closer(&self, open: char) -> Option<char> {
+        self.pairs.iter().find(|(o, _)| *o == open).map(|(_, c)| *c)
+    }
+
+    /// Anything that isn't an opener has to close the innermost open
+    /// bracket, so stray characters count as corruption too.
+    pub fn check(&self, line: &str) -> Status {
+        let mut expected = vec![];
+        for (i, c) in line.chars().enumerate() {
+            if let Some(close) = self.closer(c) {
+                expected.push(close);
+            } else if expected.last() == Some(&c) {
+                expected.pop();
+            } else {
+                return Status::Corrupted { expected: expected.last().copied(), found: c, column: i + 1 };
+            }
+        }
+
+        if expected.is_empty() {
+            Status::Balanced
+        } else {
+            Status::Incomplete { completion: expected.iter().rev().collect() }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn corrupted(expected: char, found: char, column: usize) -> Status {
+        Status::Corrupted { expected: Some(expected), found, column }
+    }
+
+    fn incomplete(completion: &str) -> Status {
+        Status::Incomplete { completion: completion.to_string() }
+    }
+
+    #[test]
+    fn test_example_lines() {
+        let brackets = Brackets::default();
+        assert_eq!(incomplete("}}]])})]"), brackets.check("[({(<(())[]>[[{[]{<()<>>"));
+        assert_eq!(corrupted(']', '}', 13), brackets.check("{([(<{}[<>[]}>{[]{[(<()>"));
+        assert_eq!(corrupted(']', ')', 9), brackets.check("[[<[([]))<([[{}[[()]]]"));
+        assert_eq!(corrupted('>', ')', 11), brackets.check("[<(<(<(<{}))><([]([]()"));
+        assert_eq!(Status::Balanced, brackets.check("<([{}])>"));
+        assert_eq!(Status::Balanced, brackets.check(""));
+    }
+
+    #[test]
+    fn test_close_with_nothing_open() {
+        let brackets = Brackets::default();
+        assert_eq!(Status::Corrupted { expected: None, found: ')', column: 3 }, brackets.check("[])"));
+        assert_eq!(corrupted(']', 'x', 2), brackets.check("[x]"));
+    }
+
+    #[test]
+    fn test_custom_pairs() -> Result<(), String> {
+        let brackets = Brackets::new(&[('/', '\\'), ('a', 'z')])?;
+        assert_eq!(incomplete("z\\"), brackets.check("/a/\\"));
+        assert_eq!(corrupted('\\', ')', 2), brackets.check("/)"));
+        assert_eq!(Status::Corrupted { expected: None, found: '(', column: 1 }, brackets.check("()"));
+
+        assert_eq!(Err("'|' is used by more than one bracket".to_string()), Brackets::new(&[('|', '|')]));
+        assert!(Brackets::new(&[('(', ')'), ('[', '(')]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_display() {
+        let brackets = Brackets::default();
+        assert_eq!("corrupted at column 13: expected ']' but found '}'", brackets.check("{([(<{}[<>[]}>{[]{[(<()>").to_string());
+        assert_eq!("corrupted at column 1: found ')' with nothing open", brackets.check(")").to_string());
+        assert_eq!("incomplete: complete with ])", brackets.check("([").to_string());
+    }
+}
@@ -1,101 +1,119 @@
+use std::env;
 use std::error::Error;
-use aoc_utils::{Puzzle, run_all};
-use std::collections::HashMap;
+use aoc_utils::{Puzzle, read_file, run_all};
+use aoc_utils::bigint::BigUint;
+use crate::brackets::{Brackets, Status};
+
+mod brackets;
 
 fn main() -> Result<(), Box<dyn Error>> {
-    run_all("./rust-2021/inputs/day_10.in", Box::new(Day10 {}))
+    let args = env::args().collect::<Vec<_>>();
+    match args.get(1).map(|s| s.as_str()) {
+        Some("check") => {
+            // pairs are written back to back, like "()[]{}<>", and their
+            // error points split by commas, like "3,57,1197,25137"
+            let day = match args.get(2) {
+                Some(pairs) => {
+                    let chars = pairs.chars().collect::<Vec<_>>();
+                    if chars.len() % 2 != 0 {
+                        return Err("pairs need an opener and a closer each".into());
+                    }
+                    let brackets = Brackets::new(&chars.chunks(2).map(|p| (p[0], p[1])).collect::<Vec<_>>())?;
+                    let points = args.get(3).ok_or("usage: day_10 check [<pairs> <error points>]")?
+                        .split(',')
+                        .map(|p| p.parse())
+                        .collect::<Result<Vec<_>, _>>()?;
+                    Day10::new(brackets, &points)?
+                },
+                None => Day10::default()
+            };
+            let input = read_file("./rust-2021/inputs/day_10.in")?;
+            for line in &input {
+                println!("{}", day.brackets.check(line));
+            }
+            println!("syntax error score: {}", day.calculate_part_1(&input));
+            println!("completion score: {}", day.calculate_part_2(&input));
+            Ok(())
+        },
+        _ => run_all("./rust-2021/inputs/day_10.in", Box::new(Day10::default()))
+    }
 }
 
-type Input = Vec<Vec<u8>>;
-type Output = i64;
+type Input = Vec<String>;
+/// Completion scores grow fivefold with every closer, so long completions
+/// outgrow any fixed width.
+type Output = BigUint;
 
-struct Day10;
+/// The puzzle's points for an illegal ')', ']', '}' and '>'.
+const ERROR_POINTS: [u64; 4] = [3, 57, 1197, 25137];
 
+struct Day10 {
+    brackets: Brackets,
+    /// Points for an illegal closer, in the order of the pair table.
+    error_points: Vec<u64>
+}
 
+impl Default for Day10 {
+    fn default() -> Self {
+        Day10::new(Brackets::default(), &ERROR_POINTS).expect("the puzzle scores every default pair")
+    }
+}
 
-impl Puzzle<Input, Output> for Day10 {
-    fn parse(&self, contents: Vec<String>) -> Result<Input, Box<dyn Error>> {
-        Ok(contents.iter()
-            .map(|s| s.bytes().collect::<Vec<_>>())
-            .collect::<Vec<_>>())
+impl Day10 {
+    fn new(brackets: Brackets, error_points: &[u64]) -> Result<Self, String> {
+        if error_points.len() != brackets.pairs().len() {
+            return Err(format!("{} bracket pairs need {} error points, not {}",
+                               brackets.pairs().len(), brackets.pairs().len(), error_points.len()));
+        }
+        Ok(Day10 { brackets, error_points: error_points.to_vec() })
     }
 
-    fn calculate_part_1(&self, input: &Input) -> Output {
-        let score_mapping  = HashMap::<u8, (u8, i64)>::from([
-            (')' as u8, ('(' as u8, 3)),
-            (']' as u8, ('[' as u8, 57)),
-            ('}' as u8, ('{' as u8, 1197)),
-            ('>' as u8, ('<' as u8, 25137))
-        ]);
-
-        let mut results = 0;
-
-        for line in input {
-            let mut stack = vec![];
-            for c in line {
-                if score_mapping.contains_key(c) {
-                    let (open, score) = score_mapping[c];
-                    if let Some(last) = stack.last() {
-                        if !open.eq(last) {
-                            results += score;
-                            break;
-                        } else {
-                            stack.pop();
-                        }
-                    }
-                } else {
-                    stack.push(c.clone());
-                }
-            }
+    /// Where `closer` sits in the pair table.
+    fn pair_of(&self, closer: char) -> Option<usize> {
+        self.brackets.pairs().iter().position(|(_, close)| *close == closer)
+    }
+
+    /// Points for the first illegal character on a corrupted line. Closers
+    /// score their pair's error points; anything else isn't a bracket and
+    /// scores nothing.
+    fn syntax_error_score(&self, status: &Status) -> Option<u64> {
+        match status {
+            Status::Corrupted { found, .. } => Some(self.pair_of(*found).map_or(0, |i| self.error_points[i])),
+            _ => None
         }
+    }
 
-        results
+    /// Each closer is worth its place in the pair table, from 1, and every
+    /// character multiplies what came before by 5.
+    fn completion_score(&self, status: &Status) -> Option<Output> {
+        match status {
+            Status::Incomplete { completion } => Some(completion.chars()
+                .map(|c| self.pair_of(c).expect("completions only close") as u64 + 1)
+                .fold(BigUint::zero(), |score, points| score * BigUint::from(5) + BigUint::from(points))),
+            _ => None
+        }
     }
+}
 
-    fn calculate_part_2(&self, input: &Input) -> Output {
-        let score_mapping = HashMap::<u8, (u8, i64)>::from([
-            (')' as u8, ('(' as u8, 1)),
-            (']' as u8, ('[' as u8, 2)),
-            ('}' as u8, ('{' as u8, 3)),
-            ('>' as u8, ('<' as u8, 4))
-        ]);
-        let mapping = HashMap::<u8, u8>::from([
-            ('(' as u8, ')' as u8),
-            ('[' as u8, ']' as u8),
-            ('{' as u8, '}' as u8),
-            ('<' as u8, '>' as u8),
-        ]);
-
-        let mut scores = vec![];
-        'outer: for line in input {
-            let mut stack = vec![];
-            for c in line {
-                if score_mapping.contains_key(c) {
-                    let (open, _) = score_mapping[c];
-                    if let Some(last) = stack.last() {
-                        if open.eq(last) {
-                            stack.pop();
-                        } else {
-                            continue 'outer;
-                        }
-                    }
-                } else {
-                    stack.push(c.clone());
-                }
-            }
+impl Puzzle<Input, Output> for Day10 {
+    fn parse(&self, contents: Vec<String>) -> Result<Input, Box<dyn Error>> {
+        Ok(contents)
+    }
 
-            let mut calc_score = 0;
-            while let Some(item) = stack.pop() {
-                let op = mapping[&item];
-                let (_, score) = score_mapping[&op];
-                calc_score = (calc_score * 5) + score;
-            }
+    fn calculate_part_1(&self, input: &Input) -> Output {
+        input.iter()
+            .filter_map(|line| self.syntax_error_score(&self.brackets.check(line)))
+            .map(BigUint::from)
+            .sum()
+    }
 
-            scores.push(calc_score);
-        }
+    fn calculate_part_2(&self, input: &Input) -> Output {
+        let mut scores = input.iter()
+            .filter_map(|line| self.completion_score(&self.brackets.check(line)))
+            .collect::<Vec<_>>();
 
         scores.sort();
-        scores[scores.len() / 2]
+        scores.get(scores.len() / 2).cloned().unwrap_or_default()
     }
 }
 
@@ -106,13 +124,49 @@ mod test {
 
     #[test]
     fn test_part_one() -> Result<(), Box<dyn Error>> {
-        assert_eq!(26397, run_part_one("./inputs/day_10_test.in", Box::new(Day10 {}))?);
+        assert_eq!(BigUint::from(26397), run_part_one("./inputs/day_10_test.in", Box::new(Day10::default()))?);
         Ok(())
     }
 
     #[test]
     fn test_part_two() -> Result<(), Box<dyn Error>> {
-        assert_eq!(288957, run_part_two("./inputs/day_10_test.in", Box::new(Day10 {}))?);
+        assert_eq!(BigUint::from(288957), run_part_two("./inputs/day_10_test.in", Box::new(Day10::default()))?);
         Ok(())
     }
+
+    #[test]
+    fn test_scores() {
+        let day = Day10::default();
+        assert_eq!(Some(1197), day.syntax_error_score(&day.brackets.check("{([(<{}[<>[]}>{[]{[(<()>")));
+        assert_eq!(None, day.syntax_error_score(&day.brackets.check("[({(<(())[]>[[{[]{<()<>>")));
+        assert_eq!(Some(BigUint::from(288957)), day.completion_score(&day.brackets.check("[({(<(())[]>[[{[]{<()<>>")));
+        assert_eq!(None, day.completion_score(&Status::Balanced));
+    }
+
+    #[test]
+    fn test_custom_pair_scores() -> Result<(), String> {
+        let day = Day10::new(Brackets::new(&[('/', '\\'), ('a', 'z')])?, &[7, 11])?;
+        assert_eq!(Some(11), day.syntax_error_score(&day.brackets.check("/z")));
+        assert_eq!(Some(7), day.syntax_error_score(&day.brackets.check("a\\")));
+        assert_eq!(Some(0), day.syntax_error_score(&day.brackets.check("/)")));
+        assert_eq!(Some(BigUint::from(2 * 5 + 1)), day.completion_score(&day.brackets.check("/a")));
+
+        let lines = ["/z", "a\\", "/a/"].map(String::from).to_vec();
+        assert_eq!(BigUint::from(18), day.calculate_part_1(&lines));
+
+        assert_eq!(Err("2 bracket pairs need 2 error points, not 4".to_string()),
+                   Day10::new(Brackets::new(&[('/', '\\'), ('a', 'z')])?, &ERROR_POINTS).map(|_| ()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_long_completions() {
+        // 5^40 + ... + 5 + 1, far past an i64
+        let day = Day10::default();
+        let line = "(".repeat(41);
+        let expected = (0..41).fold(BigUint::zero(), |score, _| score * BigUint::from(5) + BigUint::from(1));
+        assert_eq!(Some(expected.clone()), day.completion_score(&day.brackets.check(&line)));
+        assert_eq!("11368683772161602973937988281", expected.to_string());
+        assert_eq!(expected, day.calculate_part_2(&vec![line]));
+    }
 }
@@ -0,0 +1,89 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+use crate::{CaveGraph, Node};
+
+fn quote(name: &str) -> String {
+    format!("\"{}\"", name.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Renders the cave system as a Graphviz graph: start and end as double
+/// circles, big caves as boxes and small caves as ellipses. If `highlight`
+/// is given, its caves and edges are drawn in red, and each edge it uses is
+/// labelled with the steps that take it.
+pub fn to_dot(graph: &CaveGraph, highlight: Option<&[usize]>) -> String {
+    let path = highlight.unwrap_or_default();
+    let mut steps: HashMap<(usize, usize), Vec<usize>> = HashMap::new();
+    for (i, pair) in path.windows(2).enumerate() {
+        steps.entry((pair[0].min(pair[1]), pair[0].max(pair[1]))).or_default().push(i + 1);
+    }
+
+    let mut out = String::new();
+    writeln!(out, "graph caves {{").unwrap();
+
+    for (id, node) in graph.nodes.iter().enumerate() {
+        let shape = match node {
+            Node::Start | Node::End => "doublecircle",
+            Node::Big(_) => "box",
+            Node::Small(_) => "ellipse"
+        };
+        let colour = if path.contains(&id) { ", color=red, penwidth=2" } else { "" };
+        writeln!(out, "    {} [shape={}{}];", quote(node.name()), shape, colour).unwrap();
+    }
+
+    for (a, neighbours) in graph.neighbours.iter().enumerate() {
+        for b in neighbours.iter().filter(|b| a < **b) {
+            write!(out, "    {} -- {}", quote(graph.nodes[a].name()), quote(graph.nodes[*b].name())).unwrap();
+            if let Some(steps) = steps.get(&(a, *b)) {
+                let steps = steps.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+                write!(out, " [color=red, penwidth=2, label=\"{}\"]", steps.join(",")).unwrap();
+            }
+            writeln!(out, ";").unwrap();
+        }
+    }
+
+    writeln!(out, "}}").unwrap();
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use aoc_utils::Puzzle;
+    use crate::{Day12, NameRule};
+    use super::*;
+
+    fn graph(lines: &[&str]) -> CaveGraph {
+        Day12 { rule: NameRule::ByCase }.parse(lines.iter().map(|s| s.to_string()).collect()).unwrap()
+    }
+
+    #[test]
+    fn test_to_dot() {
+        let graph = graph(&["start-A", "A-b", "A-end"]);
+        assert_eq!("\
+graph caves {
+    \"start\" [shape=doublecircle];
+    \"A\" [shape=box];
+    \"b\" [shape=ellipse];
+    \"end\" [shape=doublecircle];
+    \"start\" -- \"A\";
+    \"A\" -- \"b\";
+    \"A\" -- \"end\";
+}
+", to_dot(&graph, None));
+    }
+
+    #[test]
+    fn test_to_dot_highlights_a_path() {
+        let graph = graph(&["start-A", "A-b", "A-end"]);
+        let dot = to_dot(&graph, Some(&[0, 1, 2, 1, 3]));
+
+        assert!(dot.contains("    \"b\" [shape=ellipse, color=red, penwidth=2];\n"));
+        assert!(dot.contains("    \"start\" -- \"A\" [color=red, penwidth=2, label=\"1\"];\n"));
+        assert!(dot.contains("    \"A\" -- \"b\" [color=red, penwidth=2, label=\"2,3\"];\n"));
+        assert!(dot.contains("    \"A\" -- \"end\" [color=red, penwidth=2, label=\"4\"];\n"));
+    }
+
+    #[test]
+    fn test_quote() {
+        assert_eq!("\"a\\\"b\\\\c\"", quote("a\"b\\c"));
+    }
+}
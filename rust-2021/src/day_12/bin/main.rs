@@ -1,106 +1,233 @@
 use std::collections::{HashMap, HashSet};
+use std::env;
 use std::error::Error;
-use std::rc::Rc;
-use aoc_utils::{Puzzle, run_all};
+use aoc_utils::{Puzzle, read_file, run_all};
+use crate::dot::to_dot;
+use crate::policy::{Capacities, EACH_SMALL_ONCE, ONE_SMALL_TWICE, Revisits, VisitPolicy};
+
+mod dot;
+mod paths;
+mod policy;
 
 fn main() -> Result<(), Box<dyn Error>> {
-    run_all("./rust-2021/inputs/day_12.in", Box::new(Day12 { }))
+    let args = env::args().collect::<Vec<_>>();
+    let usage = "usage: day_12 (count | paths | dot) [revisits=K | capacities=cave:N,...] [--big cave,...] [--highlight N]";
+    let mode = match args.get(1).map(|s| s.as_str()) {
+        None => return run_all("./rust-2021/inputs/day_12.in", Box::new(Day12 { rule: NameRule::ByCase })),
+        Some("count") => Mode::Count,
+        Some("paths") => Mode::Paths,
+        Some("dot") => match args.iter().position(|a| a == "--highlight") {
+            Some(i) => Mode::Dot(Some(args.get(i + 1).ok_or(usage)?.parse()?)),
+            None => Mode::Dot(None)
+        },
+        _ => return Err(usage.into())
+    };
+
+    let rule = match args.iter().position(|a| a == "--big") {
+        Some(i) => {
+            let names = args.get(i + 1).ok_or(usage)?.split(',').map(String::from).collect::<HashSet<_>>();
+            NameRule::Custom(Box::new(move |s| names.contains(s)))
+        },
+        None => NameRule::ByCase
+    };
+    let graph = Day12 { rule }.parse(read_file("./rust-2021/inputs/day_12.in")?)?;
+
+    // Whatever's left once the flags and their values are taken out has to
+    // be a single policy.
+    let mut rest = vec![];
+    let mut i = 2;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--big" => i += 2,
+            "--highlight" if matches!(mode, Mode::Dot(_)) => i += 2,
+            other => {
+                rest.push(other);
+                i += 1;
+            }
+        }
+    }
+
+    let policy = match rest.as_slice() {
+        [] => None,
+        [policy] => Some(policy.split_once('=').ok_or(usage)?),
+        _ => return Err(usage.into())
+    };
+    match policy {
+        None => report(&graph, &EACH_SMALL_ONCE, mode),
+        Some(("revisits", k)) => report(&graph, &Revisits(k.parse()?), mode),
+        Some(("capacities", caves)) => {
+            let mut capacities = HashMap::new();
+            for cave in caves.split(',') {
+                let (name, capacity) = cave.split_once(':').ok_or(usage)?;
+                capacities.insert(name.to_string(), capacity.parse()?);
+            }
+            report(&graph, &Capacities(capacities), mode)
+        },
+        _ => Err(usage.into())
+    }
 }
 
-struct Day12;
+enum Mode {
+    Count,
+    Paths,
+    /// Optionally highlighting the path with this index in `paths` order.
+    Dot(Option<usize>)
+}
+
+fn report<P: VisitPolicy>(graph: &CaveGraph, policy: &P, mode: Mode) -> Result<(), Box<dyn Error>> {
+    match mode {
+        Mode::Count => println!("{}", graph.count_paths(policy)),
+        Mode::Paths => {
+            for path in graph.paths(policy) {
+                println!("{}", graph.describe(&path));
+            }
+        },
+        Mode::Dot(highlight) => {
+            let path = match highlight {
+                Some(n) => Some(graph.paths(policy).nth(n).ok_or_else(|| format!("there's no path {}", n))?),
+                None => None
+            };
+            print!("{}", to_dot(graph, path.as_deref()));
+        }
+    }
+    Ok(())
+}
+
+struct Day12 {
+    rule: NameRule
+}
 
-type Graph = HashMap<Rc<Node>, HashSet<Rc<Node>>>;
-type Output = i32;
+type Output = u64;
 
 #[derive(PartialEq, Eq, Hash, Clone, Debug)]
 enum Node {
     Start, End, Big(String), Small(String)
 }
 
+/// How cave names are told apart. `start` and `end` are always those caves.
+enum NameRule {
+    /// Names without lowercase letters are big caves and the rest are small,
+    /// as in the puzzle.
+    ByCase,
+    /// Names the function accepts are big caves.
+    Custom(Box<dyn Fn(&str) -> bool>)
+}
+
+impl NameRule {
+    fn classify(&self, s: &str) -> Node {
+        let big = match self {
+            NameRule::ByCase => !s.chars().any(|c| c.is_lowercase()),
+            NameRule::Custom(is_big) => is_big(s)
+        };
+
+        match s {
+            "start" => Node::Start,
+            "end" => Node::End,
+            _ if big => Node::Big(s.to_string()),
+            _ => Node::Small(s.to_string())
+        }
+    }
+}
+
 impl Node {
-    fn from(s: &str) -> Self {
-        return if s.eq("start") {
-            Node::Start
-        } else if s.eq("end") {
-            Node::End
-        } else if s.to_string().chars().all(|c| c.is_lowercase()) {
-            Node::Small(s.to_string())
-        } else if s.to_string().chars().all(|c| c.is_uppercase()) {
-            Node::Big(s.to_string())
-        } else {
-            panic!("invalid input");
+    fn name(&self) -> &str {
+        match self {
+            Node::Start => "start",
+            Node::End => "end",
+            Node::Big(name) | Node::Small(name) => name
         }
     }
 }
 
-impl Day12 {
-
-    fn traverse(&self, nodes: &Graph, limit: usize) -> Vec<Vec<Rc<Node>>> {
-        let mut paths = vec![];
-
-        let mut stack = vec![];
-        stack.push((vec![Rc::new(Node::Start)], false));
-
-        while let Some((path, hit_limit)) = stack.pop() {
-            let node = &path[path.len() - 1];
-            let child_nodes = &nodes[node];
-            for child in child_nodes {
-                match child.as_ref() {
-                    Node::End => {
-                        let mut end_path = path.clone();
-                        end_path.push(child.clone());
-                        paths.push(end_path);
-                    },
-                    Node::Start => continue,
-                    Node::Big(val) => {
-                        let mut branch = path.clone();
-                        branch.push(child.clone());
-                        stack.push((branch, hit_limit));
-                    },
-                    Node::Small(val) => {
-                        if hit_limit && path.contains(&child) { continue; }
-                        let would_hit_limit = if hit_limit { hit_limit } else {
-                            path.iter()
-                            .filter(|n| n.eq(&child))
-                            .count() + 1 >= limit
-                        };
-
-                        let mut branch = path.clone();
-                        branch.push(child.clone());
-                        stack.push((branch, would_hit_limit ));
-                    }
-                }
+/// The cave system with every cave interned to a small id, in order of first
+/// appearance, so a set of caves fits in a `u64` bitmask.
+struct CaveGraph {
+    nodes: Vec<Node>,
+    neighbours: Vec<Vec<usize>>,
+    start: usize,
+    end: usize
+}
+
+impl CaveGraph {
+    const MAX_CAVES: usize = 64;
+
+    fn from_edges(edges: &[(Node, Node)]) -> Result<Self, Box<dyn Error>> {
+        let mut ids = HashMap::new();
+        let mut nodes = vec![];
+        let mut neighbours: Vec<Vec<usize>> = vec![];
+
+        for (a, b) in edges {
+            if let (Node::Big(a), Node::Big(b)) = (a, b) {
+                return Err(format!("big caves {} and {} are connected, so paths could go back and forth between them forever", a, b).into());
             }
+            let mut intern = |node: &Node| *ids.entry(node.clone()).or_insert_with(|| {
+                nodes.push(node.clone());
+                neighbours.push(vec![]);
+                nodes.len() - 1
+            });
+            let (a, b) = (intern(a), intern(b));
+            neighbours[a].push(b);
+            neighbours[b].push(a);
+        }
+
+        if nodes.len() > CaveGraph::MAX_CAVES {
+            return Err(format!("{} caves is more than the {} that fit in a bitmask", nodes.len(), CaveGraph::MAX_CAVES).into());
+        }
+        for list in neighbours.iter_mut() {
+            list.sort_unstable();
+            list.dedup();
         }
 
-        paths
+        let start = *ids.get(&Node::Start).ok_or("there's no start cave")?;
+        let end = *ids.get(&Node::End).ok_or("there's no end cave")?;
+        Ok(CaveGraph { nodes, neighbours, start, end })
     }
-}
 
-impl Puzzle<Graph, Output> for Day12 {
-    fn parse(&self, contents: Vec<String>) -> Result<Graph, Box<dyn Error>> {
-        let mut connections = HashMap::new();
-        for line in contents {
-            let split = line.split('-').collect::<Vec<&str>>();
+    fn describe(&self, path: &[usize]) -> String {
+        path.iter().map(|id| self.nodes[*id].name()).collect::<Vec<_>>().join(",")
+    }
+
+    /// How many paths lead from start to end under `policy`, without
+    /// building any of them.
+    fn count_paths<P: VisitPolicy>(&self, policy: &P) -> u64 {
+        self.count_from(policy, self.start, policy.initial(self), &mut HashMap::new())
+    }
+
+    fn count_from<P: VisitPolicy>(&self, policy: &P, node: usize, state: P::State, memo: &mut HashMap<(usize, P::State), u64>) -> u64 {
+        if let Some(count) = memo.get(&(node, state.clone())) {
+            return *count;
+        }
+
+        let mut count = policy.finishes(self, node, &state) as u64;
+        if policy.continues(self, node, &state) {
+            count += self.neighbours[node].iter()
+                .filter_map(|next| policy.enter(self, *next, &state).map(|state| (*next, state)))
+                .map(|(next, state)| self.count_from(policy, next, state, memo))
+                .sum::<u64>();
+        }
 
-            connections.entry(Rc::new(Node::from(split[0])))
-                .or_insert(HashSet::new())
-                .insert(Rc::new(Node::from(split[1])));
+        memo.insert((node, state), count);
+        count
+    }
+}
 
-            connections.entry(Rc::new(Node::from(split[1])))
-                .or_insert(HashSet::new())
-                .insert(Rc::new(Node::from(split[0])));
+impl Puzzle<CaveGraph, Output> for Day12 {
+    fn parse(&self, contents: Vec<String>) -> Result<CaveGraph, Box<dyn Error>> {
+        let mut edges = vec![];
+        for line in contents {
+            let (a, b) = line.split_once('-').ok_or_else(|| format!("expected a-b but got {:?}", line))?;
+            edges.push((self.rule.classify(a), self.rule.classify(b)));
         }
 
-        Ok(connections)
+        CaveGraph::from_edges(&edges)
     }
 
-    fn calculate_part_1(&self, input: &Graph) -> Output {
-        self.traverse(input, 1).len() as i32
+    fn calculate_part_1(&self, input: &CaveGraph) -> Output {
+        input.count_paths(&EACH_SMALL_ONCE)
     }
 
-    fn calculate_part_2(&self, input: &Graph) -> Output {
-        self.traverse(input, 2).len() as i32
+    fn calculate_part_2(&self, input: &CaveGraph) -> Output {
+        input.count_paths(&ONE_SMALL_TWICE)
     }
 }
 
@@ -111,38 +238,94 @@ mod test {
 
     #[test]
     fn test_part_one_example_1() -> Result<(), Box<dyn Error>> {
-        assert_eq!(10, run_part_one("./inputs/day_12_test.in", Box::new(Day12 { }))?);
+        assert_eq!(10, run_part_one("./inputs/day_12_test.in", Box::new(Day12 { rule: NameRule::ByCase }))?);
         Ok(())
     }
 
     #[test]
     fn test_part_one_example_2() -> Result<(), Box<dyn Error>> {
-        assert_eq!(19, run_part_one("./inputs/day_12_test_1.in", Box::new(Day12 { }))?);
+        assert_eq!(19, run_part_one("./inputs/day_12_test_1.in", Box::new(Day12 { rule: NameRule::ByCase }))?);
         Ok(())
     }
 
     #[test]
     fn test_part_one_example_3() -> Result<(), Box<dyn Error>> {
-        assert_eq!(226, run_part_one("./inputs/day_12_test_2.in", Box::new(Day12 { }))?);
+        assert_eq!(226, run_part_one("./inputs/day_12_test_2.in", Box::new(Day12 { rule: NameRule::ByCase }))?);
         Ok(())
     }
 
     #[test]
     fn test_part_two_example_1() -> Result<(), Box<dyn Error>> {
-        assert_eq!(36, run_part_two("./inputs/day_12_test.in", Box::new(Day12 { }))?);
+        assert_eq!(36, run_part_two("./inputs/day_12_test.in", Box::new(Day12 { rule: NameRule::ByCase }))?);
         Ok(())
     }
 
     #[test]
     fn test_part_two_example_2() -> Result<(), Box<dyn Error>> {
-        assert_eq!(103, run_part_two("./inputs/day_12_test_1.in", Box::new(Day12 { }))?);
+        assert_eq!(103, run_part_two("./inputs/day_12_test_1.in", Box::new(Day12 { rule: NameRule::ByCase }))?);
         Ok(())
     }
 
     #[test]
     fn test_part_two_example_3() -> Result<(), Box<dyn Error>> {
-        assert_eq!(3509, run_part_two("./inputs/day_12_test_2.in", Box::new(Day12 { }))?);
+        assert_eq!(3509, run_part_two("./inputs/day_12_test_2.in", Box::new(Day12 { rule: NameRule::ByCase }))?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_count_matches_enumeration() -> Result<(), Box<dyn Error>> {
+        for path in ["./inputs/day_12_test.in", "./inputs/day_12_test_1.in", "./inputs/day_12_test_2.in"] {
+            let graph = Day12 { rule: NameRule::ByCase }.parse(read_file(path)?)?;
+            for revisits in 0..3 {
+                assert_eq!(graph.paths(&Revisits(revisits)).count() as u64, graph.count_paths(&Revisits(revisits)));
+            }
+        }
         Ok(())
     }
 
+    #[test]
+    fn test_paths() -> Result<(), Box<dyn Error>> {
+        let graph = Day12 { rule: NameRule::ByCase }.parse(read_file("./inputs/day_12_test.in")?)?;
+        let mut paths = graph.paths(&EACH_SMALL_ONCE).map(|p| graph.describe(&p)).collect::<Vec<_>>();
+        paths.sort();
+
+        assert_eq!(10, paths.len());
+        assert_eq!("start,A,b,A,c,A,end", paths[0]);
+        assert_eq!("start,b,end", paths[9]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let parse = |lines: &[&str]| Day12 { rule: NameRule::ByCase }.parse(lines.iter().map(|s| s.to_string()).collect());
+        assert!(parse(&["start-A", "A"]).is_err());
+        assert!(parse(&["start-A"]).is_err());
+
+        let many = (0..64).map(|i| format!("start-c{}", "a".repeat(i + 1))).collect::<Vec<_>>();
+        let many = many.iter().map(|s| s.as_str()).chain(["start-end"]).collect::<Vec<_>>();
+        assert!(parse(&many).is_err());
+    }
+
+    #[test]
+    fn test_connected_big_caves() {
+        let lines = ["start-A", "A-B", "B-end"].map(String::from).to_vec();
+        let error = Day12 { rule: NameRule::ByCase }.parse(lines.clone()).err().unwrap();
+        assert_eq!("big caves A and B are connected, so paths could go back and forth between them forever", error.to_string());
+
+        let rule = NameRule::Custom(Box::new(|s| s == "A"));
+        assert_eq!(1, Day12 { rule }.parse(lines).unwrap().count_paths(&EACH_SMALL_ONCE));
+    }
+
+    #[test]
+    fn test_name_rules() {
+        assert_eq!(Node::Start, NameRule::ByCase.classify("start"));
+        assert_eq!(Node::Big("AB1".to_string()), NameRule::ByCase.classify("AB1"));
+        assert_eq!(Node::Small("Ab".to_string()), NameRule::ByCase.classify("Ab"));
+        assert_eq!(Node::Big("7".to_string()), NameRule::ByCase.classify("7"));
+
+        let rule = NameRule::Custom(Box::new(|s| s.starts_with('_')));
+        assert_eq!(Node::Big("_hall".to_string()), rule.classify("_hall"));
+        assert_eq!(Node::Small("ROOM".to_string()), rule.classify("ROOM"));
+        assert_eq!(Node::End, rule.classify("end"));
+    }
 }
\ No newline at end of file
@@ -0,0 +1,101 @@
+use crate::CaveGraph;
+use crate::policy::VisitPolicy;
+
+/// Walks the paths from start to end depth first, trying neighbours in id
+/// order, so only the current path is held in memory and the order is the
+/// same on every run.
+pub struct Paths<'a, P: VisitPolicy> {
+    graph: &'a CaveGraph,
+    policy: &'a P,
+    path: Vec<usize>,
+    /// For each cave on `path`, the policy's state on entering it and the
+    /// index of the next neighbour to try.
+    frames: Vec<(P::State, usize)>
+}
+
+impl<P: VisitPolicy> Iterator for Paths<'_, P> {
+    type Item = Vec<usize>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let node = *self.path.last()?;
+            let (state, next_index) = self.frames.last_mut()?;
+
+            let Some(next) = self.graph.neighbours[node].get(*next_index).copied() else {
+                self.path.pop();
+                self.frames.pop();
+                continue;
+            };
+            *next_index += 1;
+
+            let Some(state) = self.policy.enter(self.graph, next, state) else { continue };
+            let finishes = self.policy.finishes(self.graph, next, &state);
+            self.path.push(next);
+            let path = finishes.then(|| self.path.clone());
+            if self.policy.continues(self.graph, next, &state) {
+                self.frames.push((state, 0));
+            } else {
+                self.path.pop();
+            }
+            if path.is_some() {
+                return path;
+            }
+        }
+    }
+}
+
+impl CaveGraph {
+    /// Every path from start to end as cave ids, produced one at a time.
+    /// `count_paths` is far cheaper when only the number is wanted.
+    pub fn paths<'a, P: VisitPolicy>(&'a self, policy: &'a P) -> Paths<'a, P> {
+        Paths { graph: self, policy, path: vec![self.start], frames: vec![(policy.initial(self), 0)] }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::error::Error;
+    use aoc_utils::{Puzzle, read_file};
+    use crate::{Day12, NameRule};
+    use crate::policy::{EACH_SMALL_ONCE, ONE_SMALL_TWICE};
+    use super::*;
+
+    fn example() -> Result<CaveGraph, Box<dyn Error>> {
+        Day12 { rule: NameRule::ByCase }.parse(read_file("./inputs/day_12_test.in")?)
+    }
+
+    #[test]
+    fn test_paths_come_in_neighbour_order() -> Result<(), Box<dyn Error>> {
+        let graph = example()?;
+        let paths = graph.paths(&EACH_SMALL_ONCE).map(|p| graph.describe(&p)).collect::<Vec<_>>();
+
+        // Caves are numbered start, A, b, c, d, end from the input.
+        assert_eq!(vec![
+            "start,A,b,A,c,A,end", "start,A,b,A,end", "start,A,b,end", "start,A,c,A,b,A,end", "start,A,c,A,b,end",
+            "start,A,c,A,end", "start,A,end", "start,b,A,c,A,end", "start,b,A,end", "start,b,end"
+        ], paths);
+        Ok(())
+    }
+
+    #[test]
+    fn test_paths_are_lazy() -> Result<(), Box<dyn Error>> {
+        let graph = Day12 { rule: NameRule::ByCase }.parse(read_file("./inputs/day_12_test_2.in")?)?;
+        let mut paths = graph.paths(&ONE_SMALL_TWICE);
+
+        let first = paths.next().unwrap();
+        assert_eq!(Some(&graph.start), first.first());
+        assert_eq!(Some(&graph.end), first.last());
+        assert_eq!(3508, paths.count());
+        Ok(())
+    }
+
+    #[test]
+    fn test_paths_are_distinct() -> Result<(), Box<dyn Error>> {
+        let graph = example()?;
+        let mut paths = graph.paths(&ONE_SMALL_TWICE).collect::<Vec<_>>();
+        paths.sort();
+        paths.dedup();
+        assert_eq!(36, paths.len());
+        Ok(())
+    }
+}
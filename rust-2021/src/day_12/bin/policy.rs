@@ -0,0 +1,208 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use crate::{CaveGraph, Node};
+
+/// Decides which caves a path may step into. The state should hold only
+/// what the decision needs, as paths are counted once per distinct
+/// (cave, state) pair.
+pub trait VisitPolicy {
+    type State: Clone + Eq + Hash;
+
+    /// The state of a path that's only at the start cave.
+    fn initial(&self, graph: &CaveGraph) -> Self::State;
+
+    /// The state after stepping into `node`, or `None` if it can't be
+    /// entered.
+    fn enter(&self, graph: &CaveGraph, node: usize, state: &Self::State) -> Option<Self::State>;
+
+    /// Whether a path that has just entered `node` is a finished path from
+    /// start to end. By default, any path that reaches the end.
+    fn finishes(&self, graph: &CaveGraph, node: usize, _state: &Self::State) -> bool {
+        node == graph.end
+    }
+
+    /// Whether a path may go on from `node` after entering it. By default,
+    /// anywhere but the end.
+    fn continues(&self, graph: &CaveGraph, node: usize, _state: &Self::State) -> bool {
+        node != graph.end
+    }
+}
+
+/// Small caves may be gone back into `k` times in total, whether that's one
+/// cave `k` more times or `k` caves once more each. Big caves are free and
+/// the start is never re-entered.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Revisits(pub usize);
+
+/// Part 1: each small cave at most once.
+pub const EACH_SMALL_ONCE: Revisits = Revisits(0);
+
+/// Part 2: a single small cave may be visited twice.
+pub const ONE_SMALL_TWICE: Revisits = Revisits(1);
+
+impl VisitPolicy for Revisits {
+    /// The small caves visited so far as a bitmask, and the revisits left.
+    type State = (u64, usize);
+
+    fn initial(&self, graph: &CaveGraph) -> Self::State {
+        (1 << graph.start, self.0)
+    }
+
+    fn enter(&self, graph: &CaveGraph, node: usize, &(visited, budget): &Self::State) -> Option<Self::State> {
+        let bit = 1 << node;
+        match graph.nodes[node] {
+            Node::Start => None,
+            Node::Small(_) if visited & bit != 0 => budget.checked_sub(1).map(|budget| (visited, budget)),
+            Node::Small(_) => Some((visited | bit, budget)),
+            _ => Some((visited, budget))
+        }
+    }
+}
+
+/// Each named cave may be visited as many times as its capacity. Unnamed
+/// small caves and the start have a capacity of 1; unnamed big caves have
+/// no limit. Naming the end gives it a capacity too and lets paths carry on
+/// through it, finishing each time they arrive.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Capacities(pub HashMap<String, usize>);
+
+impl Capacities {
+    fn capacity(&self, node: &Node) -> Option<usize> {
+        match self.0.get(node.name()) {
+            Some(capacity) => Some(*capacity),
+            None => match node {
+                Node::Big(_) | Node::End => None,
+                Node::Start | Node::Small(_) => Some(1)
+            }
+        }
+    }
+}
+
+impl VisitPolicy for Capacities {
+    /// How many times each cave has been entered, indexed by cave id.
+    type State = Vec<usize>;
+
+    fn initial(&self, graph: &CaveGraph) -> Self::State {
+        let mut visits = vec![0; graph.nodes.len()];
+        visits[graph.start] = 1;
+        visits
+    }
+
+    fn enter(&self, graph: &CaveGraph, node: usize, visits: &Self::State) -> Option<Self::State> {
+        if self.capacity(&graph.nodes[node]).is_some_and(|capacity| visits[node] >= capacity) {
+            return None;
+        }
+
+        let mut visits = visits.clone();
+        visits[node] += 1;
+        Some(visits)
+    }
+
+    fn continues(&self, graph: &CaveGraph, node: usize, _visits: &Self::State) -> bool {
+        node != graph.end || self.0.contains_key(graph.nodes[node].name())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::error::Error;
+    use aoc_utils::{Puzzle, read_file};
+    use crate::{Day12, NameRule};
+    use super::*;
+
+    fn example() -> Result<CaveGraph, Box<dyn Error>> {
+        Day12 { rule: NameRule::ByCase }.parse(read_file("./inputs/day_12_test.in")?)
+    }
+
+    #[test]
+    fn test_revisits() -> Result<(), Box<dyn Error>> {
+        let graph = example()?;
+        assert_eq!(10, graph.count_paths(&EACH_SMALL_ONCE));
+        assert_eq!(36, graph.count_paths(&ONE_SMALL_TWICE));
+
+        // Two revisits allow b three times, which one small cave twice can't.
+        let paths = graph.paths(&Revisits(2)).map(|p| graph.describe(&p)).collect::<Vec<_>>();
+        assert!(paths.contains(&"start,b,A,b,A,b,end".to_string()));
+        assert_eq!(paths.len() as u64, graph.count_paths(&Revisits(2)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_default_capacities_match_each_small_once() -> Result<(), Box<dyn Error>> {
+        let graph = example()?;
+        assert_eq!(10, graph.count_paths(&Capacities::default()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_capacities() -> Result<(), Box<dyn Error>> {
+        let graph = example()?;
+        let capacities = |pairs: &[(&str, usize)]| Capacities(pairs.iter().map(|(n, c)| (n.to_string(), *c)).collect());
+
+        // b twice and c never: from the 36 one-small-twice paths keep the
+        // ones that skip c and revisit nothing but b.
+        let policy = capacities(&[("b", 2), ("c", 0)]);
+        let paths = graph.paths(&policy).map(|p| graph.describe(&p)).collect::<Vec<_>>();
+        assert!(paths.iter().all(|p| !p.contains('c')));
+        assert!(paths.contains(&"start,A,b,A,b,end".to_string()));
+        assert_eq!(paths.len() as u64, graph.count_paths(&policy));
+
+        // Capping A at once leaves only paths through it a single time.
+        assert_eq!(4, graph.count_paths(&capacities(&[("A", 1)])));
+        Ok(())
+    }
+
+    #[test]
+    fn test_capacities_past_255() -> Result<(), Box<dyn Error>> {
+        let lines = ["start-A", "A-b", "A-end"].iter().map(|s| s.to_string()).collect();
+        let graph = Day12 { rule: NameRule::ByCase }.parse(lines)?;
+
+        // start,A,(b,A)*k,end for k up to 299
+        let policy = Capacities([("A", 300), ("b", 300)].iter().map(|(n, c)| (n.to_string(), *c)).collect());
+        assert_eq!(300, graph.count_paths(&policy));
+        Ok(())
+    }
+
+    #[test]
+    fn test_through_the_end() -> Result<(), Box<dyn Error>> {
+        let graph = example()?;
+        let policy = Capacities([("end", 2)].iter().map(|(n, c)| (n.to_string(), *c)).collect());
+        let paths = graph.paths(&policy).map(|p| graph.describe(&p)).collect::<Vec<_>>();
+
+        assert!(paths.contains(&"start,A,end".to_string()));
+        assert!(paths.contains(&"start,A,end,b,end".to_string()));
+        assert!(paths.iter().all(|p| p.ends_with(",end") && p.matches("end").count() <= 2));
+        assert_eq!(paths.len() as u64, graph.count_paths(&policy));
+        Ok(())
+    }
+
+    /// Only finishes paths that have been through c.
+    struct ThroughC;
+
+    impl VisitPolicy for ThroughC {
+        type State = (<Revisits as VisitPolicy>::State, bool);
+
+        fn initial(&self, graph: &CaveGraph) -> Self::State {
+            (EACH_SMALL_ONCE.initial(graph), false)
+        }
+
+        fn enter(&self, graph: &CaveGraph, node: usize, (state, seen_c): &Self::State) -> Option<Self::State> {
+            let state = EACH_SMALL_ONCE.enter(graph, node, state)?;
+            Some((state, *seen_c || graph.nodes[node].name() == "c"))
+        }
+
+        fn finishes(&self, graph: &CaveGraph, node: usize, (_, seen_c): &Self::State) -> bool {
+            node == graph.end && *seen_c
+        }
+    }
+
+    #[test]
+    fn test_policy_decides_where_paths_finish() -> Result<(), Box<dyn Error>> {
+        let graph = example()?;
+        let paths = graph.paths(&ThroughC).map(|p| graph.describe(&p)).collect::<Vec<_>>();
+
+        assert_eq!(vec!["start,A,b,A,c,A,end", "start,A,c,A,b,A,end", "start,A,c,A,b,end", "start,A,c,A,end", "start,b,A,c,A,end"], paths);
+        assert_eq!(5, graph.count_paths(&ThroughC));
+        Ok(())
+    }
+}
@@ -1,12 +1,43 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::env;
 use std::error::Error;
-use aoc_utils::{Puzzle, run_all};
+use aoc_utils::{Puzzle, read_file, run_all};
+use aoc_utils::matrix::{Exact, Modulo};
+
+mod matrix;
 
 fn main() -> Result<(), Box<dyn Error>> {
-    run_all("./rust-2021/inputs/day_14.in", Box::new(Day14(10, 40)))
+    let args = env::args().collect::<Vec<_>>();
+    let Some(steps) = args.get(1) else {
+        return run_all("./rust-2021/inputs/day_14.in", Box::new(Day14(10, 40)));
+    };
+
+    let steps = steps.parse()?;
+    let equipment = Day14(steps, steps).parse(read_file("./rust-2021/inputs/day_14.in")?)?;
+    match args.get(2) {
+        Some(at) if at == "at" => {
+            let k = args.get(3).ok_or("usage: day_14 <steps> at <position>")?.parse()?;
+            match equipment.element_at(steps, k)? {
+                Some(element) => println!("{}", element as char),
+                None => println!("the polymer is shorter than that")
+            }
+        },
+        Some(p) => {
+            let ring = Modulo::new(p.parse()?).ok_or("the modulus must be at least 2")?;
+            print_counts(equipment.element_counts(steps, &ring));
+        },
+        None => print_counts(equipment.element_counts(steps, &Exact))
+    }
+    Ok(())
+}
+
+fn print_counts<V: std::fmt::Display>(counts: Vec<(u8, V)>) {
+    for (element, count) in counts {
+        println!("{}: {}", element as char, count);
+    }
 }
 
-struct Day14(i32, i32);
+struct Day14(u64, u64);
 
 #[derive(Debug, Clone)]
 struct PolymerizationEquipment {
@@ -16,33 +47,135 @@ struct PolymerizationEquipment {
 }
 
 impl PolymerizationEquipment {
+    /// The most steps' worth of pair lengths `element_at` works out.
+    const MAX_ROWS: usize = 10_000;
+
+    /// Inserts into every pair that has a rule; pairs without one stay as
+    /// they are.
     fn grow(&mut self) {
         let mut new_template = HashMap::new();
 
         for ((left, right), value) in &self.template {
-            let spawn = &self.rules[&(*left, *right)];
-            *new_template.entry((*left, *spawn)).or_insert(0) += value;
-            *new_template.entry((*spawn, *right)).or_insert(0) += value;
+            match self.rules.get(&(*left, *right)) {
+                Some(spawn) => {
+                    *new_template.entry((*left, *spawn)).or_insert(0) += value;
+                    *new_template.entry((*spawn, *right)).or_insert(0) += value;
+                },
+                None => *new_template.entry((*left, *right)).or_insert(0) += value
+            }
         }
 
         self.template = new_template;
     }
 
-    fn calculate(&self) -> i64 {
-        let mut occurances = HashMap::new();
+    fn after(&self, steps: u64) -> Self {
+        let mut equipment = self.clone();
+        for _ in 0..steps {
+            equipment.grow();
+        }
+        equipment
+    }
+
+    /// How many of each element the polymer holds. Every element but the
+    /// last starts exactly one pair, and growing never changes the last.
+    fn histogram(&self) -> BTreeMap<u8, i64> {
+        let mut histogram = BTreeMap::new();
         for ((left, _), amount) in &self.template {
-            *occurances.entry(left).or_insert(0) += amount;
+            *histogram.entry(*left).or_insert(0) += amount;
+        }
+        if let Some(last) = self.template_raw.last() {
+            *histogram.entry(*last).or_insert(0) += 1;
         }
+        histogram.retain(|_, amount| *amount > 0);
+        histogram
+    }
+
+    fn calculate(&self) -> i64 {
+        let histogram = self.histogram();
+        let max = histogram.values().max().unwrap_or(&0);
+        let min = histogram.values().min().unwrap_or(&0);
 
-        *occurances.entry(&self.template_raw[self.template_raw.len() - 1]).or_insert(0) += 1;
+        max - min
+    }
 
-        let mut nums = vec![];
-        for (_, value) in occurances {
-            nums.push(value);
+    /// The element at position `k` of the template after `steps` steps, or
+    /// `None` past the end. Rather than building the polymer, this picks the
+    /// pair that covers `k` and keeps choosing the half that covers it, using
+    /// how long each pair gets after each number of steps.
+    ///
+    /// Only whether a length is more than `k` matters, so lengths are capped
+    /// just above it. Once a step leaves every capped length as it was, later
+    /// steps can't change them either, so no more are worked out; the descent
+    /// through those identical steps repeats, and whole cycles of it are
+    /// skipped. Slow, linearly growing rules can take about `k` steps to get
+    /// there, so this fails rather than work out more than `MAX_ROWS` of them.
+    fn element_at(&self, steps: u64, k: u64) -> Result<Option<u8>, String> {
+        let cap = k as u128 + 1;
+
+        // lengths[n][pair] is how many elements the pair grows to after n
+        // steps, counting its left element but not its right one, up to `cap`.
+        let mut lengths = vec![HashMap::new()];
+        let mut pairs = self.template_raw.windows(2).map(|w| (w[0], w[1])).collect::<Vec<_>>();
+        pairs.extend(self.rules.iter().flat_map(|((l, r), s)| [(*l, *r), (*l, *s), (*s, *r)]));
+        for pair in &pairs {
+            lengths[0].insert(*pair, 1u128);
+        }
+        for _ in 0..steps {
+            let previous = &lengths[lengths.len() - 1];
+            let next = pairs.iter().map(|&(l, r)| {
+                let length = match self.rules.get(&(l, r)) {
+                    Some(s) => (previous[&(l, *s)] + previous[&(*s, r)]).min(cap),
+                    None => previous[&(l, r)]
+                };
+                ((l, r), length)
+            }).collect::<HashMap<_, _>>();
+            if &next == previous {
+                break;
+            }
+            if lengths.len() > Self::MAX_ROWS {
+                return Err(format!("finding position {} takes more than {} steps of pair lengths, as the rules grow the polymer too slowly", k, Self::MAX_ROWS));
+            }
+            lengths.push(next);
+        }
+        let last = lengths.len() as u64 - 1;
+        let row = |n: u64| &lengths[n.min(last) as usize];
+
+        let mut k = k as u128;
+        for pair in self.template_raw.windows(2) {
+            let length = row(steps)[&(pair[0], pair[1])];
+            if k >= length {
+                k -= length;
+                continue;
+            }
+
+            let (mut left, mut right) = (pair[0], pair[1]);
+            // the step each pair was last seen at since `k` last changed,
+            // while the lengths are still the unchanging ones
+            let mut seen = HashMap::new();
+            let mut n = steps;
+            while n > 0 && k > 0 {
+                let Some(spawn) = self.rules.get(&(left, right)) else { break };
+                if n > last {
+                    if let Some(before) = seen.insert((left, right), n) {
+                        let cycle = before - n;
+                        n -= (n - last - 1) / cycle * cycle;
+                    }
+                }
+
+                let length = row(n - 1)[&(left, *spawn)];
+                if k < length {
+                    right = *spawn;
+                } else {
+                    k -= length;
+                    left = *spawn;
+                    seen.clear();
+                }
+                n -= 1;
+            }
+            return Ok(Some(left));
         }
-        nums.sort();
 
-        nums[nums.len() - 1] - nums[0]
+        Ok(if k == 0 { self.template_raw.last().copied() } else { None })
     }
 }
 
@@ -72,21 +205,11 @@ impl Puzzle<Input, Output> for Day14 {
     }
 
     fn calculate_part_1(&self, input: &Input) -> Output {
-        let mut input = input.clone();
-        for _ in 0..self.0 {
-            input.grow();
-        }
-
-        input.calculate()
+        input.after(self.0).calculate()
     }
 
     fn calculate_part_2(&self, input: &Input) -> Output {
-        let mut input = input.clone();
-        for _ in 0..self.1 {
-            input.grow();
-        }
-
-        input.calculate()
+        input.after(self.1).calculate()
     }
 }
 
@@ -106,4 +229,101 @@ mod test {
         assert_eq!(2188189693529, run_part_two("./inputs/day_14_test.in", Box::new(Day14(10, 40)))?);
         Ok(())
     }
-}
\ No newline at end of file
+
+    /// Builds the polymer the slow way.
+    fn expand(equipment: &PolymerizationEquipment, steps: u64) -> Vec<u8> {
+        let mut polymer = equipment.template_raw.clone();
+        for _ in 0..steps {
+            let mut next = vec![polymer[0]];
+            for pair in polymer.windows(2) {
+                if let Some(spawn) = equipment.rules.get(&(pair[0], pair[1])) {
+                    next.push(*spawn);
+                }
+                next.push(pair[1]);
+            }
+            polymer = next;
+        }
+        polymer
+    }
+
+    fn sparse() -> Result<PolymerizationEquipment, Box<dyn Error>> {
+        let lines = ["ABA", "", "AB -> A"];
+        Day14(2, 2).parse(lines.iter().map(|s| s.to_string()).collect())
+    }
+
+    #[test]
+    fn test_pairs_without_rules_persist() -> Result<(), Box<dyn Error>> {
+        let equipment = sparse()?;
+        assert_eq!(b"AAABA".to_vec(), expand(&equipment, 2));
+        assert_eq!(BTreeMap::from([(b'A', 4), (b'B', 1)]), equipment.after(2).histogram());
+        Ok(())
+    }
+
+    #[test]
+    fn test_histogram() -> Result<(), Box<dyn Error>> {
+        let equipment = Day14(10, 40).parse(read_file("./inputs/day_14_test.in")?)?;
+        assert_eq!(BTreeMap::from([(b'B', 1749), (b'C', 298), (b'H', 161), (b'N', 865)]), equipment.after(10).histogram());
+        assert_eq!(BTreeMap::from([(b'B', 1), (b'C', 1), (b'N', 2)]), equipment.histogram());
+        Ok(())
+    }
+
+    #[test]
+    fn test_element_at_matches_expansion() -> Result<(), Box<dyn Error>> {
+        let equipment = Day14(10, 40).parse(read_file("./inputs/day_14_test.in")?)?;
+        for equipment in [equipment, sparse()?, fibonacci()?] {
+            for steps in 0..7 {
+                let polymer = expand(&equipment, steps);
+                for (k, element) in polymer.iter().enumerate() {
+                    assert_eq!(Ok(Some(*element)), equipment.element_at(steps, k as u64));
+                }
+                assert_eq!(Ok(None), equipment.element_at(steps, polymer.len() as u64));
+            }
+        }
+        Ok(())
+    }
+
+    /// "AAA" with AA -> B and AB -> A, whose pairs grow like the Fibonacci
+    /// numbers.
+    fn fibonacci() -> Result<PolymerizationEquipment, Box<dyn Error>> {
+        let lines = ["AAA", "", "AA -> B", "AB -> A"];
+        Day14(2, 2).parse(lines.iter().map(|s| s.to_string()).collect())
+    }
+
+    #[test]
+    fn test_element_at_long_polymers() -> Result<(), Box<dyn Error>> {
+        let equipment = Day14(10, 40).parse(read_file("./inputs/day_14_test.in")?)?;
+        for equipment in [equipment, fibonacci()?] {
+            let polymer = expand(&equipment, 13);
+            for k in (0..polymer.len()).step_by(97).chain(polymer.len() - 20..polymer.len()) {
+                assert_eq!(Ok(Some(polymer[k])), equipment.element_at(13, k as u64));
+            }
+            assert_eq!(Ok(None), equipment.element_at(13, polymer.len() as u64));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_element_at_past_u64_lengths() -> Result<(), Box<dyn Error>> {
+        let equipment = fibonacci()?;
+        // The first AA is longer than u64::MAX from step 92 on, so the last
+        // position falls inside it rather than past it, at a B.
+        for steps in [92, 100, 1_000_000_000_000_000_000] {
+            assert_eq!(Ok(Some(b'B')), equipment.element_at(steps, u64::MAX));
+            assert_eq!(Ok(Some(b'A')), equipment.element_at(steps, u64::MAX - 1));
+            assert_eq!(Ok(Some(b'A')), equipment.element_at(steps, 0));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_element_at_slow_growth() -> Result<(), Box<dyn Error>> {
+        // AB -> A only adds one A a step, giving A...ABA
+        let equipment = sparse()?;
+        let steps = 1_000_000_000_000;
+        assert_eq!(Ok(Some(b'A')), equipment.element_at(steps, 9_000));
+        assert_eq!(Ok(Some(b'B')), equipment.element_at(5_000, 5_001));
+        assert!(equipment.element_at(steps, 20_000).is_err());
+        assert!(equipment.element_at(steps, u64::MAX).is_err());
+        Ok(())
+    }
+}
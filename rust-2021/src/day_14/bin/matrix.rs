@@ -0,0 +1,137 @@
+use aoc_utils::matrix::{Matrix, Ring};
+use crate::PolymerizationEquipment;
+
+impl PolymerizationEquipment {
+    /// Every element in the template or the rules, sorted.
+    fn elements(&self) -> Vec<u8> {
+        let mut elements = self.template_raw.clone();
+        for ((left, right), spawn) in &self.rules {
+            elements.extend([*left, *right, *spawn]);
+        }
+        elements.sort_unstable();
+        elements.dedup();
+        elements
+    }
+
+    /// How many of each element the polymer holds after `steps` steps,
+    /// sorted by element, by raising the pair transition matrix to the
+    /// `steps`th power.
+    pub fn element_counts<R: Ring>(&self, steps: u64, ring: &R) -> Vec<(u8, R::Value)> {
+        let elements = self.elements();
+        let e = elements.len();
+        let index = |c: u8| elements.binary_search(&c).expect("every element is listed");
+        let pairs = e * e;
+
+        // Column `left * e + right` says where one of that pair goes in a step.
+        let mut transitions = Matrix::zero(pairs, ring);
+        for left in 0..e {
+            for right in 0..e {
+                let from = left * e + right;
+                match self.rules.get(&(elements[left], elements[right])) {
+                    Some(spawn) => {
+                        transitions.add_to(left * e + index(*spawn), from, 1, ring);
+                        transitions.add_to(index(*spawn) * e + right, from, 1, ring);
+                    },
+                    None => transitions.add_to(from, from, 1, ring)
+                }
+            }
+        }
+
+        let mut counts = vec![ring.value(0); pairs];
+        for pair in self.template_raw.windows(2) {
+            let i = index(pair[0]) * e + index(pair[1]);
+            counts[i] = ring.add(&counts[i], &ring.value(1));
+        }
+        let counts = transitions.pow_apply(steps, counts, ring);
+
+        // Every element but the last starts exactly one pair, and the last
+        // element never changes.
+        let mut totals = vec![ring.value(0); e];
+        for (i, count) in counts.iter().enumerate() {
+            totals[i / e] = ring.add(&totals[i / e], count);
+        }
+        if let Some(last) = self.template_raw.last() {
+            let i = index(*last);
+            totals[i] = ring.add(&totals[i], &ring.value(1));
+        }
+
+        elements.into_iter().zip(totals).collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::error::Error;
+    use aoc_utils::{Puzzle, read_file};
+    use aoc_utils::bigint::BigUint;
+    use aoc_utils::matrix::{Exact, Modulo};
+    use crate::Day14;
+    use super::*;
+
+    fn example() -> Result<PolymerizationEquipment, Box<dyn Error>> {
+        Day14(10, 40).parse(read_file("./inputs/day_14_test.in")?)
+    }
+
+    fn exact(counts: Vec<(u8, BigUint)>) -> Vec<(char, u64)> {
+        counts.into_iter().map(|(c, n)| (c as char, n.to_u64().unwrap())).collect()
+    }
+
+    #[test]
+    fn test_exact_counts_after_10_steps() -> Result<(), Box<dyn Error>> {
+        let counts = example()?.element_counts(10, &Exact);
+        assert_eq!(vec![('B', 1749), ('C', 298), ('H', 161), ('N', 865)], exact(counts));
+        Ok(())
+    }
+
+    #[test]
+    fn test_exact_counts_after_40_steps() -> Result<(), Box<dyn Error>> {
+        let counts = exact(example()?.element_counts(40, &Exact));
+        let max = counts.iter().map(|(_, n)| *n).max().unwrap();
+        let min = counts.iter().map(|(_, n)| *n).min().unwrap();
+        assert_eq!(2188189693529, max - min);
+        Ok(())
+    }
+
+    #[test]
+    fn test_zero_steps_counts_the_template() -> Result<(), Box<dyn Error>> {
+        let counts = example()?.element_counts(0, &Exact);
+        assert_eq!(vec![('B', 1), ('C', 1), ('H', 0), ('N', 2)], exact(counts));
+        Ok(())
+    }
+
+    #[test]
+    fn test_modulo_agrees_with_exact() -> Result<(), Box<dyn Error>> {
+        let equipment = example()?;
+        let p = 1_000_000_007;
+        let exact = equipment.element_counts(200, &Exact);
+        let modulo = equipment.element_counts(200, &Modulo::new(p).unwrap());
+
+        for ((c, big), (d, small)) in exact.into_iter().zip(modulo) {
+            assert_eq!(c, d);
+            assert_eq!(big.rem_u64(p), small);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_modulo_after_a_trillion_steps() -> Result<(), Box<dyn Error>> {
+        // With a rule for every pair the length after n steps is 3 * 2^n + 1.
+        let p = 998_244_353;
+        let ring = Modulo::new(p).unwrap();
+        let steps = 1_000_000_000_000u64;
+        let total = example()?.element_counts(steps, &ring).iter().fold(0, |sum, (_, n)| ring.add(&sum, n));
+
+        let mut power = 1;
+        let mut base = 2;
+        let mut n = steps;
+        while n > 0 {
+            if n & 1 == 1 {
+                power = ring.mul(&power, &base);
+            }
+            base = ring.mul(&base, &base);
+            n >>= 1;
+        }
+        assert_eq!(ring.add(&ring.mul(&3, &power), &1), total);
+        Ok(())
+    }
+}
@@ -0,0 +1,439 @@
+use std::cmp::max;
+use std::error::Error;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use crate::bits::{BitReader, BitWriter};
+use crate::{DecodeError, Decoder, PACKET_NAMES, Packet, PacketBody};
+
+/// One line per packet in pre-order, closing parens trail the last line of
+/// each operator.
+fn collect_lines(packet: &Packet, lines: &mut Vec<String>) {
+    enum Step<'a> {
+        Line(&'a Packet, usize),
+        Close
+    }
+
+    let mut stack = vec![Step::Line(packet, 0)];
+    while let Some(step) = stack.pop() {
+        let (packet, depth) = match step {
+            Step::Line(packet, depth) => (packet, depth),
+            Step::Close => {
+                if let Some(last) = lines.last_mut() {
+                    last.push(')');
+                }
+                continue;
+            }
+        };
+
+        let indent = "  ".repeat(depth);
+        match &packet.body {
+            PacketBody::Literal(value) => lines.push(format!("{}(lit v{} {})", indent, packet.version, value)),
+            body => {
+                lines.push(format!("{}({} v{}", indent, body.name(), packet.version));
+                stack.push(Step::Close);
+                stack.extend(body.children().iter().rev().map(|p| Step::Line(p, depth + 1)));
+            }
+        }
+    }
+}
+
+impl Display for Packet {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut lines = vec![];
+        collect_lines(self, &mut lines);
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+/// Decodes a transmission and prints it as an S-expression, with the bit
+/// offset of every packet in a trailing comment.
+pub fn disassemble(bytes: &[u8]) -> Result<String, DecodeError> {
+    let mut decoder = Decoder { reader: BitReader::new(bytes), path: vec![], offsets: Some(vec![]) };
+    let packet = decoder.parse_packet()?;
+
+    let mut lines = vec![];
+    collect_lines(&packet, &mut lines);
+
+    let width = lines.iter().map(|l| l.len()).max().unwrap_or_default();
+    Ok(lines.iter()
+        .zip(decoder.offsets.unwrap_or_default())
+        .map(|(line, offset)| format!("{:<width$}  ; bit {}", line, offset, width = width))
+        .collect::<Vec<_>>()
+        .join("\n"))
+}
+
+pub fn encode(packet: &Packet) -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut writer = BitWriter::new();
+    encode_packet(packet, &mut writer)?;
+    Ok(writer.into_bytes())
+}
+
+/// Writes packets in pre-order from a stack. Operators sized in bits get
+/// their length patched in once their last sub-packet is written.
+fn encode_packet(packet: &Packet, writer: &mut BitWriter) -> Result<(), Box<dyn Error>> {
+    enum Step<'a> {
+        Encode(&'a Packet),
+        Patch { mark: usize, count: usize }
+    }
+
+    let mut stack = vec![Step::Encode(packet)];
+    while let Some(step) = stack.pop() {
+        let packet = match step {
+            Step::Encode(packet) => packet,
+            Step::Patch { mark, count } => {
+                let length = writer.position() - mark - 15;
+                if length >= 1 << 15 {
+                    return Err(format!("{} sub-packets taking {} bits don't fit in one operator", count, length).into());
+                }
+                writer.patch(mark, length as u64, 15);
+                continue;
+            }
+        };
+
+        if packet.version > 7 {
+            return Err(format!("version {} doesn't fit in 3 bits", packet.version).into());
+        }
+
+        writer.write(packet.version as u64, 3);
+        writer.write(packet.body.type_id() as u64, 3);
+
+        match &packet.body {
+            PacketBody::Literal(value) => {
+                let groups = max(1, (64 - value.leading_zeros() as usize).div_ceil(4));
+                for group in (0..groups).rev() {
+                    writer.write((group > 0) as u64, 1);
+                    writer.write((value >> (group * 4)) & 0xF, 4);
+                }
+            },
+            body => {
+                let packets = body.children();
+                if packets.len() < 1 << 11 {
+                    writer.write(1, 1);
+                    writer.write(packets.len() as u64, 11);
+                } else {
+                    writer.write(0, 1);
+                    stack.push(Step::Patch { mark: writer.position(), count: packets.len() });
+                    writer.write(0, 15);
+                }
+                stack.extend(packets.iter().rev().map(Step::Encode));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+pub fn convert_bytes_to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02X}", b)).collect()
+}
+
+/// Compiles S-expression source straight to a hex transmission.
+pub fn assemble(src: &str) -> Result<String, Box<dyn Error>> {
+    let packet = compile(src)?;
+    Ok(convert_bytes_to_hex(&encode(&packet)?))
+}
+
+#[derive(Debug, PartialEq)]
+pub struct CompileError {
+    line: usize,
+    column: usize,
+    message: String
+}
+
+impl Display for CompileError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+impl Error for CompileError {}
+
+#[derive(Debug, PartialEq)]
+enum Token<'a> {
+    Open,
+    Close,
+    Atom(&'a str)
+}
+
+/// Splits source into parens and atoms, each with its 1-based line and
+/// column. `;` starts a comment that runs to the end of the line.
+fn tokenize(src: &str) -> Vec<(Token<'_>, usize, usize)> {
+    let mut tokens = vec![];
+    for (line_no, line) in src.lines().enumerate() {
+        let line = line.split(';').next().unwrap_or_default();
+        let mut atom_start = None;
+
+        for (i, c) in line.char_indices().chain([(line.len(), ' ')]) {
+            let is_delimiter = c.is_whitespace() || c == '(' || c == ')';
+            if is_delimiter {
+                if let Some(start) = atom_start.take() {
+                    tokens.push((Token::Atom(&line[start..i]), line_no + 1, start + 1));
+                }
+            } else if atom_start.is_none() {
+                atom_start = Some(i);
+            }
+
+            match c {
+                '(' => tokens.push((Token::Open, line_no + 1, i + 1)),
+                ')' => tokens.push((Token::Close, line_no + 1, i + 1)),
+                _ => {}
+            }
+        }
+    }
+    tokens
+}
+
+struct Parser<'a> {
+    tokens: Vec<(Token<'a>, usize, usize)>,
+    pos: usize
+}
+
+impl<'a> Parser<'a> {
+    fn error(&self, message: String) -> CompileError {
+        let (line, column) = match self.tokens.get(self.pos) {
+            Some((_, line, column)) => (*line, *column),
+            None => self.tokens.last().map_or((1, 1), |(_, line, column)| (*line, column + 1))
+        };
+        CompileError { line, column, message }
+    }
+
+    fn next(&mut self) -> Result<&Token<'a>, CompileError> {
+        match self.tokens.get(self.pos) {
+            Some((token, _, _)) => {
+                self.pos += 1;
+                Ok(token)
+            },
+            None => Err(self.error("unexpected end of input".to_string()))
+        }
+    }
+
+    fn peek(&self) -> Option<&Token<'a>> {
+        self.tokens.get(self.pos).map(|(token, _, _)| token)
+    }
+
+    /// Reads one packet, keeping operators whose sub-packets are still
+    /// being read on a stack rather than in nested calls.
+    fn parse_packet(&mut self) -> Result<Packet, CompileError> {
+        let mut open: Vec<(u8, u8, Vec<Packet>)> = vec![];
+        loop {
+            let (packet_type, version) = self.parse_header()?;
+            let mut finished = if packet_type == 4 {
+                let value = match self.peek() {
+                    Some(Token::Atom(atom)) => atom.parse::<u64>()
+                        .map_err(|_| self.error(format!("invalid literal {:?}", atom)))?,
+                    _ => return Err(self.error("expected a literal value".to_string()))
+                };
+                self.pos += 1;
+
+                if self.peek() != Some(&Token::Close) {
+                    return Err(self.error("expected ')'".to_string()));
+                }
+                self.pos += 1;
+                Some(Packet::from(version, packet_type, PacketBody::Literal(value)))
+            } else {
+                open.push((packet_type, version, vec![]));
+                None
+            };
+
+            // Close operators for as long as a ')' follows.
+            loop {
+                let Some((_, _, packets)) = open.last_mut() else {
+                    return Ok(finished.expect("the outermost packet is finished once nothing is open"));
+                };
+                packets.extend(finished.take());
+                if self.peek() != Some(&Token::Close) {
+                    break;
+                }
+                self.pos += 1;
+
+                let (packet_type, version, packets) = open.pop().expect("an operator is open");
+                let body = PacketBody::from(packet_type, packets).expect("only literals have type 4");
+                finished = Some(Packet::from(version, packet_type, body));
+            }
+        }
+    }
+
+    /// Reads a packet's opening paren, operator name and optional version.
+    fn parse_header(&mut self) -> Result<(u8, u8), CompileError> {
+        match self.peek() {
+            Some(Token::Open) => self.pos += 1,
+            Some(_) => return Err(self.error("expected '('".to_string())),
+            None => return Err(self.error("unexpected end of input".to_string()))
+        }
+
+        let name = match self.next()? {
+            Token::Atom(name) => *name,
+            _ => {
+                self.pos -= 1;
+                return Err(self.error("expected an operator name".to_string()));
+            }
+        };
+        let packet_type = match PACKET_NAMES.iter().position(|n| *n == name) {
+            Some(t) => t as u8,
+            None => {
+                self.pos -= 1;
+                return Err(self.error(format!("unknown operator {:?}", name)));
+            }
+        };
+
+        let mut version = 0;
+        if let Some(Token::Atom(atom)) = self.peek() {
+            if let Some(v) = atom.strip_prefix('v') {
+                version = match v.parse::<u8>() {
+                    Ok(v) if v <= 7 => v,
+                    _ => return Err(self.error(format!("version {:?} isn't between v0 and v7", atom)))
+                };
+                self.pos += 1;
+            }
+        }
+
+        Ok((packet_type, version))
+    }
+}
+
+/// Parses the S-expression syntax printed by `disassemble` and `Display`.
+/// Versions are optional and default to `v0`.
+pub fn compile(src: &str) -> Result<Packet, CompileError> {
+    let mut parser = Parser { tokens: tokenize(src), pos: 0 };
+    let packet = parser.parse_packet()?;
+
+    if parser.peek().is_some() {
+        return Err(parser.error("unexpected input after the outermost packet".to_string()));
+    }
+
+    Ok(packet)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{convert_bytes_to_packet, convert_hex_to_bytes};
+    use super::*;
+
+    #[test]
+    fn test_disassemble_length_type_0() {
+        let bytes = convert_hex_to_bytes("38006F45291200").unwrap();
+        let expected = [
+            "(lt v1          ; bit 0",
+            "  (lit v6 10)   ; bit 22",
+            "  (lit v2 20))  ; bit 33"
+        ].join("\n");
+        assert_eq!(expected, disassemble(&bytes).unwrap());
+    }
+
+    #[test]
+    fn test_disassemble_nested() {
+        let bytes = convert_hex_to_bytes("8A004A801A8002F478").unwrap();
+        let expected = [
+            "(min v4               ; bit 0",
+            "  (min v1             ; bit 18",
+            "    (min v5           ; bit 36",
+            "      (lit v6 15))))  ; bit 58"
+        ].join("\n");
+        assert_eq!(expected, disassemble(&bytes).unwrap());
+    }
+
+    #[test]
+    fn test_disassemble_reports_decode_errors() {
+        let bytes = convert_hex_to_bytes("38006F452912").unwrap();
+        assert_eq!(vec![1], disassemble(&bytes).unwrap_err().path);
+    }
+
+    #[test]
+    fn test_display() {
+        let bytes = convert_hex_to_bytes("9C0141080250320F1802104A08").unwrap();
+        let packet = convert_bytes_to_packet(&bytes).unwrap();
+        let expected = [
+            "(eq v4",
+            "  (sum v2",
+            "    (lit v2 1)",
+            "    (lit v4 3))",
+            "  (product v6",
+            "    (lit v0 2)",
+            "    (lit v2 2)))"
+        ].join("\n");
+        assert_eq!(expected, packet.to_string());
+    }
+
+    #[test]
+    fn test_compile_disassembly_round_trip() {
+        for hex in ["C0015000016115A2E0802F182340", "A0016C880162017C3686B18A3D4780", "9C0141080250320F1802104A08"] {
+            let bytes = convert_hex_to_bytes(hex).unwrap();
+            let decoded = convert_bytes_to_packet(&bytes).unwrap();
+            assert_eq!(decoded, compile(&disassemble(&bytes).unwrap()).unwrap());
+            assert_eq!(decoded, compile(&decoded.to_string()).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_assemble_matches_puzzle_encoding() {
+        assert_eq!("D2FE28", assemble("(lit v6 2021)").unwrap());
+        assert_eq!("EE00D40C823060", assemble("(max v7 (lit v2 1) (lit v4 2) (lit v1 3))").unwrap());
+    }
+
+    #[test]
+    fn test_assemble_round_trip() {
+        let src = "(sum v3 (lit 10) (product v1 (lit v2 0) (lit 18446744073709551615)) (eq (min) (gt v7)))";
+        let packet = compile(src).unwrap();
+        let bytes = convert_hex_to_bytes(&assemble(src).unwrap()).unwrap();
+        assert_eq!(packet, convert_bytes_to_packet(&bytes).unwrap());
+    }
+
+    #[test]
+    fn test_encode_many_sub_packets_uses_bit_length() {
+        let src = format!("(sum {})", "(lit 1) ".repeat(2500));
+        let packet = compile(&src).unwrap();
+        let bytes = encode(&packet).unwrap();
+        assert_eq!(0, bytes[0] & 0b10);
+        assert_eq!(packet, convert_bytes_to_packet(&bytes).unwrap());
+    }
+
+    #[test]
+    fn test_encode_too_many_sub_packets() {
+        let src = format!("(sum {})", "(lit 1) ".repeat(3000));
+        assert!(encode(&compile(&src).unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_decode_large_transmission() {
+        let inner = format!("(sum {})", "(lit 7) ".repeat(2000));
+        let src = format!("(sum {})", inner.repeat(200));
+        let bytes = encode(&compile(&src).unwrap()).unwrap();
+        assert!(bytes.len() > 500_000);
+
+        let packet = convert_bytes_to_packet(&bytes).unwrap();
+        assert_eq!(Ok(7 * 2000 * 200), packet.checked_value());
+    }
+
+    #[test]
+    fn test_compile_errors() {
+        let error = |src: &str| compile(src).unwrap_err().to_string();
+        assert_eq!("1:2: unknown operator \"add\"", error("(add (lit 1))"));
+        assert_eq!("1:6: version \"v8\" isn't between v0 and v7", error("(lit v8 1)"));
+        assert_eq!("1:6: invalid literal \"-1\"", error("(lit -1)"));
+        assert_eq!("1:5: expected a literal value", error("(lit)"));
+        assert_eq!("2:3: expected '('", error("(sum\n  10)"));
+        assert_eq!("1:13: unexpected end of input", error("(sum (lit 1) ; (lit 2))"));
+        assert_eq!("1:9: unexpected input after the outermost packet", error("(lit 1) (lit 2)"));
+    }
+
+    #[test]
+    fn test_deeply_nested_round_trip() {
+        let nested = |depth| format!("{}(lit 5){}", "(sum v1 ".repeat(depth), ")".repeat(depth));
+        let packet = compile(&nested(100_000)).unwrap();
+        assert_eq!(100_001, packet.depth());
+
+        let bytes = encode(&packet).unwrap();
+        let decoded = convert_bytes_to_packet(&bytes).unwrap();
+        assert_eq!(100_000, decoded.sum_of_versions());
+        assert_eq!(Ok(5), decoded.checked_value());
+
+        // every line is indented by its depth, so the text itself grows
+        // quadratically
+        let packet = compile(&nested(2000)).unwrap();
+        let text = packet.to_string();
+        assert_eq!(2001, text.lines().count());
+        assert!(text.ends_with(&format!("(lit v0 5){}", ")".repeat(2000))));
+        assert_eq!(packet, compile(&text).unwrap());
+        assert!(disassemble(&encode(&packet).unwrap()).unwrap().ends_with("; bit 36000"));
+    }
+}
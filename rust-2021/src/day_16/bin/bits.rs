@@ -0,0 +1,164 @@
+use std::cmp::min;
+
+/// Reads unsigned fields of arbitrary bit width, most significant bit first,
+/// straight out of a byte slice.
+pub struct BitReader<'a> {
+    bytes: &'a [u8],
+    pos: usize
+}
+
+impl<'a> BitReader<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        BitReader { bytes, pos: 0 }
+    }
+
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+
+    pub fn len(&self) -> usize {
+        self.bytes.len() * 8
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.len() - self.pos
+    }
+
+    /// Reads the next `n` bits (at most 64) as an unsigned number, or `None`
+    /// without moving if fewer than `n` bits are left.
+    pub fn read(&mut self, n: usize) -> Option<u64> {
+        assert!(n <= 64, "can't read more than 64 bits at once");
+        if n > self.remaining() {
+            return None;
+        }
+
+        let mut value = 0;
+        let mut left = n;
+        while left > 0 {
+            let byte = self.bytes[self.pos / 8];
+            let available = 8 - self.pos % 8;
+            let take = min(available, left);
+            let chunk = (byte >> (available - take)) & (((1u16 << take) - 1) as u8);
+
+            value = (value << take) | chunk as u64;
+            self.pos += take;
+            left -= take;
+        }
+
+        Some(value)
+    }
+}
+
+/// Appends unsigned fields of arbitrary bit width, most significant bit first.
+pub struct BitWriter {
+    bytes: Vec<u8>,
+    len: usize
+}
+
+impl BitWriter {
+    pub fn new() -> Self {
+        BitWriter { bytes: vec![], len: 0 }
+    }
+
+    pub fn position(&self) -> usize {
+        self.len
+    }
+
+    /// Writes the low `n` bits of `value`.
+    pub fn write(&mut self, value: u64, n: usize) {
+        for i in (0..n).rev() {
+            if self.len.is_multiple_of(8) {
+                self.bytes.push(0);
+            }
+            self.len += 1;
+            self.set(self.len - 1, (value >> i) & 1 == 1);
+        }
+    }
+
+    /// Overwrites `n` bits that have already been written, starting at `pos`.
+    pub fn patch(&mut self, pos: usize, value: u64, n: usize) {
+        assert!(pos + n <= self.len, "can't patch bits that haven't been written");
+        for i in 0..n {
+            self.set(pos + i, (value >> (n - 1 - i)) & 1 == 1);
+        }
+    }
+
+    fn set(&mut self, pos: usize, bit: bool) {
+        let mask = 1 << (7 - pos % 8);
+        if bit {
+            self.bytes[pos / 8] |= mask;
+        } else {
+            self.bytes[pos / 8] &= !mask;
+        }
+    }
+
+    /// The written bits, zero padded to a whole byte.
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_read_single_bits() {
+        let bytes = [0xD2, 0xFE, 0x28];
+        let mut reader = BitReader::new(&bytes);
+        let bits = (0..reader.len())
+            .map(|_| if reader.read(1) == Some(1) { '1' } else { '0' })
+            .collect::<String>();
+
+        assert_eq!("110100101111111000101000", bits);
+        assert_eq!(0, reader.remaining());
+        assert_eq!(None, reader.read(1));
+    }
+
+    #[test]
+    fn test_read_across_byte_boundaries() {
+        let bytes = [0x38, 0x00, 0x6F, 0x45];
+        let mut reader = BitReader::new(&bytes);
+        assert_eq!(Some(0b001), reader.read(3));
+        assert_eq!(Some(0b110), reader.read(3));
+        assert_eq!(Some(0b0), reader.read(1));
+        assert_eq!(Some(27), reader.read(15));
+        assert_eq!(22, reader.position());
+        assert_eq!(Some(0b1101000101), reader.read(10));
+    }
+
+    #[test]
+    fn test_read_full_width() {
+        let bytes = [0xFF, 0x00, 0xFF, 0x00, 0xFF, 0x00, 0xFF, 0x00, 0x80];
+        let mut reader = BitReader::new(&bytes);
+        assert_eq!(Some(1), reader.read(1));
+        assert_eq!(Some(0xFE01FE01FE01FE01), reader.read(64));
+    }
+
+    #[test]
+    fn test_write_and_read_back() {
+        let mut writer = BitWriter::new();
+        writer.write(0b110, 3);
+        writer.write(0b100, 3);
+        writer.write(0x7E5, 15);
+        assert_eq!(21, writer.position());
+
+        let bytes = writer.into_bytes();
+        assert_eq!(vec![0xD0, 0x3F, 0x28], bytes);
+
+        let mut reader = BitReader::new(&bytes);
+        assert_eq!(Some(0b110), reader.read(3));
+        assert_eq!(Some(0b100), reader.read(3));
+        assert_eq!(Some(0x7E5), reader.read(15));
+    }
+
+    #[test]
+    fn test_patch() {
+        let mut writer = BitWriter::new();
+        writer.write(0b1, 1);
+        writer.write(0, 15);
+        writer.write(0xFF, 8);
+        writer.patch(1, 0b101010101010101, 15);
+        assert_eq!(vec![0xD5, 0x55, 0xFF], writer.into_bytes());
+    }
+}
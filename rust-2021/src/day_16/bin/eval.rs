@@ -0,0 +1,172 @@
+use std::error::Error;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use aoc_utils::bigint::BigUint;
+use crate::{Packet, PacketBody, write_path};
+
+/// The arithmetic an evaluation mode needs. `None` means the result doesn't
+/// fit in the type.
+pub trait Number: Ord + Sized {
+    fn from_u64(n: u64) -> Self;
+    fn checked_add(&self, rhs: &Self) -> Option<Self>;
+    fn checked_mul(&self, rhs: &Self) -> Option<Self>;
+}
+
+impl Number for u64 {
+    fn from_u64(n: u64) -> Self {
+        n
+    }
+
+    fn checked_add(&self, rhs: &Self) -> Option<Self> {
+        u64::checked_add(*self, *rhs)
+    }
+
+    fn checked_mul(&self, rhs: &Self) -> Option<Self> {
+        u64::checked_mul(*self, *rhs)
+    }
+}
+
+impl Number for BigUint {
+    fn from_u64(n: u64) -> Self {
+        BigUint::from(n)
+    }
+
+    fn checked_add(&self, rhs: &Self) -> Option<Self> {
+        Some(self + rhs)
+    }
+
+    fn checked_mul(&self, rhs: &Self) -> Option<Self> {
+        Some(self * rhs)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum EvalErrorKind {
+    Overflow(&'static str),
+    Arity { operator: &'static str, expected: &'static str, found: usize }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct EvalError {
+    path: Vec<usize>,
+    kind: EvalErrorKind
+}
+
+impl Display for EvalError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write_path(f, &self.path)?;
+        match &self.kind {
+            EvalErrorKind::Overflow(operator) => write!(f, ": {} overflows", operator),
+            EvalErrorKind::Arity { operator, expected, found } =>
+                write!(f, ": {} expects {} sub-packets but has {}", operator, expected, found)
+        }
+    }
+}
+
+impl Error for EvalError {}
+
+impl Packet {
+    /// Evaluates with u64 arithmetic, failing on overflow instead of wrapping.
+    pub fn checked_value(&self) -> Result<u64, EvalError> {
+        evaluate(self)
+    }
+
+    /// Evaluates with arbitrary precision, so only malformed packets fail.
+    pub fn exact_value(&self) -> Result<BigUint, EvalError> {
+        evaluate(self)
+    }
+}
+
+fn evaluate<N: Number>(packet: &Packet) -> Result<N, EvalError> {
+    packet.try_fold(|packet, path, values: Vec<N>| {
+        let operator = packet.body.name();
+        let error = |kind| EvalError { path: path.to_vec(), kind };
+
+        let expected = match packet.body {
+            PacketBody::GreaterThan(_) | PacketBody::LessThan(_) | PacketBody::Equal(_) if values.len() != 2 => Some("exactly 2"),
+            PacketBody::Minimum(_) | PacketBody::Maximum(_) if values.is_empty() => Some("at least 1"),
+            _ => None
+        };
+        if let Some(expected) = expected {
+            return Err(error(EvalErrorKind::Arity { operator, expected, found: values.len() }));
+        }
+
+        let from_bool = |b: bool| N::from_u64(b as u64);
+        match packet.body {
+            PacketBody::Literal(v) => Ok(N::from_u64(v)),
+            PacketBody::Sum(_) => values.iter()
+                .try_fold(N::from_u64(0), |acc, v| acc.checked_add(v))
+                .ok_or_else(|| error(EvalErrorKind::Overflow(operator))),
+            PacketBody::Product(_) => values.iter()
+                .try_fold(N::from_u64(1), |acc, v| acc.checked_mul(v))
+                .ok_or_else(|| error(EvalErrorKind::Overflow(operator))),
+            PacketBody::Minimum(_) => Ok(values.into_iter().min().expect("arity is checked")),
+            PacketBody::Maximum(_) => Ok(values.into_iter().max().expect("arity is checked")),
+            PacketBody::GreaterThan(_) => Ok(from_bool(values[0] > values[1])),
+            PacketBody::LessThan(_) => Ok(from_bool(values[0] < values[1])),
+            PacketBody::Equal(_) => Ok(from_bool(values[0] == values[1]))
+        }
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use crate::asm::compile;
+    use crate::{convert_bytes_to_packet, convert_hex_to_bytes};
+    use super::*;
+
+    #[test]
+    fn test_checked_matches_exact_on_examples() {
+        for hex in ["C200B40A82", "04005AC33890", "880086C3E88112", "CE00C43D881120", "D8005AC2A8F0", "F600BC2D8F", "9C005AC2F8F0", "9C0141080250320F1802104A08"] {
+            let packet = convert_bytes_to_packet(&convert_hex_to_bytes(hex).unwrap()).unwrap();
+            let value = packet.checked_value().unwrap();
+            assert_eq!(Ok(BigUint::from(value)), packet.exact_value());
+        }
+    }
+
+    #[test]
+    fn test_product_overflow() {
+        let packet = compile("(sum (lit 1) (product (lit 4294967296) (lit 4294967296)))").unwrap();
+        let error = packet.checked_value().unwrap_err();
+        assert_eq!(EvalError { path: vec![1], kind: EvalErrorKind::Overflow("product") }, error);
+        assert_eq!("root > sub[1]: product overflows", error.to_string());
+        assert_eq!("18446744073709551617", packet.exact_value().unwrap().to_string());
+    }
+
+    #[test]
+    fn test_sum_overflow() {
+        let packet = compile("(sum (lit 18446744073709551615) (lit 1))").unwrap();
+        assert_eq!(Err(EvalError { path: vec![], kind: EvalErrorKind::Overflow("sum") }), packet.checked_value());
+        assert_eq!("18446744073709551616", packet.exact_value().unwrap().to_string());
+    }
+
+    #[test]
+    fn test_exact_comparison_of_big_values() {
+        let packet = compile("(gt (product (lit 18446744073709551615) (lit 2)) (product (lit 18446744073709551615) (lit 1)))").unwrap();
+        assert!(packet.checked_value().is_err());
+        assert_eq!(Ok(BigUint::one()), packet.exact_value());
+    }
+
+    #[test]
+    fn test_comparison_arity() {
+        let packet = compile("(sum (lit 1) (max (lit 2)) (eq (lit 1) (lit 1) (lit 1)))").unwrap();
+        let error = packet.exact_value().unwrap_err();
+        assert_eq!(EvalError { path: vec![2], kind: EvalErrorKind::Arity { operator: "eq", expected: "exactly 2", found: 3 } }, error);
+        assert_eq!("root > sub[2]: eq expects exactly 2 sub-packets but has 3", error.to_string());
+    }
+
+    #[test]
+    fn test_empty_minimum() {
+        let packet = compile("(product (lit 3) (sum (min)))").unwrap();
+        assert_eq!(
+            Err(EvalError { path: vec![1, 0], kind: EvalErrorKind::Arity { operator: "min", expected: "at least 1", found: 0 } }),
+            packet.checked_value()
+        );
+    }
+
+    #[test]
+    fn test_empty_sum_and_product() {
+        assert_eq!(Ok(0), compile("(sum)").unwrap().checked_value());
+        assert_eq!(Ok(1), compile("(product)").unwrap().checked_value());
+    }
+}
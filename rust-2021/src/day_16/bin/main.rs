@@ -1,16 +1,74 @@
+use std::{env, fs};
 use std::error::Error;
-use std::u8;
+use std::fmt;
+use std::fmt::{Debug, Display, Formatter};
 use aoc_utils::{Puzzle, run_all};
-use crate::PacketBody::Literal;
+use aoc_utils::bigint::BigUint;
+use crate::bits::BitReader;
+use crate::eval::EvalError;
+
+mod asm;
+mod bits;
+mod eval;
+mod tree;
 
 fn main() -> Result<(), Box<dyn Error>> {
-    run_all("./rust-2021/inputs/day_16.in", Box::new(Day16))
+    let args = env::args().collect::<Vec<_>>();
+    match (args.get(1).map(String::as_str), args.get(2)) {
+        (Some("disasm"), Some(hex)) => {
+            println!("{}", asm::disassemble(&convert_hex_to_bytes(hex.trim())?)?);
+            Ok(())
+        },
+        (Some("eval"), Some(hex)) => {
+            let packet = convert_bytes_to_packet(&convert_hex_to_bytes(hex.trim())?)?;
+            match packet.checked_value() {
+                Ok(value) => println!("u64: {}", value),
+                Err(e) => println!("u64: {}", e)
+            }
+            println!("exact: {}", packet.exact_value()?);
+            Ok(())
+        },
+        (Some("stats"), Some(hex)) => {
+            let packet = convert_bytes_to_packet(&convert_hex_to_bytes(hex.trim())?)?;
+            println!("depth: {}", packet.depth());
+            for (name, count) in PACKET_NAMES.iter().zip(packet.type_counts()) {
+                println!("{}: {}", name, count);
+            }
+            Ok(())
+        },
+        (Some("asm"), Some(path)) => {
+            println!("{}", asm::assemble(&fs::read_to_string(path)?)?);
+            Ok(())
+        },
+        _ => run_all("./rust-2021/inputs/day_16.in", Box::new(Day16))
+    }
 }
 
 struct Day16;
 
 type Input = Packet;
-type Output = u64;
+type Output = Answer;
+
+/// What a part comes to. Values that outgrow a `u64` are kept exactly, and
+/// packets that can't be evaluated at all are reported rather than panicked
+/// on.
+#[derive(PartialEq)]
+enum Answer {
+    Value(BigUint),
+    Invalid(EvalError)
+}
+
+impl Debug for Answer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Answer::Value(value) => write!(f, "{}", value),
+            Answer::Invalid(e) => write!(f, "{}", e)
+        }
+    }
+}
+
+/// Operator names in the S-expression syntax, indexed by packet type.
+const PACKET_NAMES: [&str; 8] = ["sum", "product", "min", "max", "lit", "gt", "lt", "eq"];
 
 #[derive(PartialEq, Debug)]
 enum PacketBody {
@@ -25,49 +83,61 @@ enum PacketBody {
 }
 
 impl PacketBody {
-    fn from(packet_type: u8, packets: Vec<Packet>) -> Self {
+    fn from(packet_type: u8, packets: Vec<Packet>) -> Option<Self> {
         match packet_type {
-            0 => PacketBody::Sum(packets),
-            1 => PacketBody::Product(packets),
-            2 => PacketBody::Minimum(packets),
-            3 => PacketBody::Maximum(packets),
-            5 => PacketBody::GreaterThan(packets),
-            6 => PacketBody::LessThan(packets),
-            7 => PacketBody::Equal(packets),
-            _ => panic!("unknown operator")
+            0 => Some(PacketBody::Sum(packets)),
+            1 => Some(PacketBody::Product(packets)),
+            2 => Some(PacketBody::Minimum(packets)),
+            3 => Some(PacketBody::Maximum(packets)),
+            5 => Some(PacketBody::GreaterThan(packets)),
+            6 => Some(PacketBody::LessThan(packets)),
+            7 => Some(PacketBody::Equal(packets)),
+            _ => None
         }
     }
 
-    fn value(&self) -> u64 {
+    fn type_id(&self) -> u8 {
         match self {
-            PacketBody::Literal(v) => v.clone(),
-            PacketBody::Equal(p) => if p[0].value() == p[1].value() { 1 } else { 0 },
-            PacketBody::LessThan(p) => if p[0].value() < p[1].value() { 1 } else { 0 },
-            PacketBody::GreaterThan(p) => if p[0].value() > p[1].value() { 1 } else { 0 },
-            PacketBody::Maximum(p) => p.iter().map(|p| p.value()).max().unwrap_or_default(),
-            PacketBody::Minimum(p) => p.iter().map(|p| p.value()).min().unwrap_or_default(),
-            PacketBody::Product(p) => p.iter().map(|p| p.value()).product(),
-            PacketBody::Sum(p) => p.iter().map(|p| p.value()).sum()
+            PacketBody::Sum(_) => 0,
+            PacketBody::Product(_) => 1,
+            PacketBody::Minimum(_) => 2,
+            PacketBody::Maximum(_) => 3,
+            PacketBody::Literal(_) => 4,
+            PacketBody::GreaterThan(_) => 5,
+            PacketBody::LessThan(_) => 6,
+            PacketBody::Equal(_) => 7
         }
     }
 
-    fn sum_of_versions(&self) -> u64 {
-        if let Literal(_) = self {
-            return 0;
-        }
+    /// The operator name used by the S-expression syntax.
+    fn name(&self) -> &'static str {
+        PACKET_NAMES[self.type_id() as usize]
+    }
 
-        let packets = match &self {
-            PacketBody::Sum(packets) => packets,
-            PacketBody::Product(packets) => packets,
-            PacketBody::Minimum(packets) => packets,
-            PacketBody::Maximum(packets) => packets,
-            PacketBody::GreaterThan(packets) => packets,
-            PacketBody::LessThan(packets) => packets,
-            PacketBody::Equal(packets) => packets,
-            _ => panic!("it shouldn't go here")
-        };
+    fn children(&self) -> &[Packet] {
+        match self {
+            PacketBody::Literal(_) => &[],
+            PacketBody::Sum(packets)
+            | PacketBody::Product(packets)
+            | PacketBody::Minimum(packets)
+            | PacketBody::Maximum(packets)
+            | PacketBody::GreaterThan(packets)
+            | PacketBody::LessThan(packets)
+            | PacketBody::Equal(packets) => packets
+        }
+    }
 
-        packets.iter().map(|p| p.sum_of_versions()).sum()
+    fn take_children(&mut self) -> Vec<Packet> {
+        match self {
+            PacketBody::Literal(_) => vec![],
+            PacketBody::Sum(packets)
+            | PacketBody::Product(packets)
+            | PacketBody::Minimum(packets)
+            | PacketBody::Maximum(packets)
+            | PacketBody::GreaterThan(packets)
+            | PacketBody::LessThan(packets)
+            | PacketBody::Equal(packets) => std::mem::take(packets)
+        }
     }
 }
 
@@ -79,12 +149,14 @@ struct Packet {
 }
 
 impl Packet {
+    #[cfg(test)]
     fn literal(header: u8, literal: u64) -> Self {
         Packet { version: header >> 3, packet_type: header & 0b111, body: PacketBody::Literal(literal) }
     }
 
+    #[cfg(test)]
     fn operation(header: u8, packets: Vec<Packet>) -> Self {
-        Packet { version: header >> 3, packet_type: header & 0b111, body: PacketBody::from(header & 0b111, packets) }
+        Packet { version: header >> 3, packet_type: header & 0b111, body: PacketBody::from(header & 0b111, packets).expect("unknown operator") }
     }
 
     fn from(version: u8, packet_type: u8, body: PacketBody) -> Self {
@@ -92,107 +164,228 @@ impl Packet {
     }
 
     fn sum_of_versions(&self) -> u64 {
-        (self.version as u64) + &self.body.sum_of_versions()
+        self.pre_order().map(|p| p.version as u64).sum()
     }
+}
 
-    fn value(&self) -> u64 {
-        self.body.value()
+#[derive(Debug, PartialEq)]
+enum DecodeErrorKind {
+    InvalidHex(char),
+    Truncated { needed: usize, available: usize },
+    UnknownType(u8),
+    LiteralOverflow,
+    LengthMismatch { expected: usize, actual: usize }
+}
+
+/// Why decoding stopped, where in the bit stream it happened and which packet
+/// it was reading, given as the indices of each sub-packet from the root down.
+#[derive(Debug, PartialEq)]
+struct DecodeError {
+    offset: usize,
+    path: Vec<usize>,
+    kind: DecodeErrorKind
+}
+
+impl Display for DecodeErrorKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeErrorKind::InvalidHex(c) => write!(f, "invalid hex digit {:?}", c),
+            DecodeErrorKind::Truncated { needed, available } =>
+                write!(f, "truncated, needed {} bits but only {} left", needed, available),
+            DecodeErrorKind::UnknownType(t) => write!(f, "unknown packet type {}", t),
+            DecodeErrorKind::LiteralOverflow => write!(f, "literal doesn't fit in 64 bits"),
+            DecodeErrorKind::LengthMismatch { expected, actual } =>
+                write!(f, "sub-packets should take {} bits but took {}", expected, actual)
+        }
     }
 }
 
-fn convert_hex_to_bits(str: &str) -> Result<String, Box<dyn Error>> {
-    let mut results = String::with_capacity(str.len() * 4);
-    for i in 0..str.len() {
-        let s = u8::from_str_radix(&str[i..i+1], 16)?;
-        results.extend(format!("{:0>4b}", s).chars());
+/// Writes a packet path as "root > sub[2] > sub[0]".
+fn write_path(f: &mut Formatter<'_>, path: &[usize]) -> fmt::Result {
+    write!(f, "root")?;
+    for i in path {
+        write!(f, " > sub[{}]", i)?;
     }
-    Ok(results)
+    Ok(())
 }
 
-fn convert_bit_string_to_packets(bits: String) -> Packet {
-    parse_packet(bits.as_str(), 0).0
+impl Display for DecodeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "bit {} at ", self.offset)?;
+        write_path(f, &self.path)?;
+        write!(f, ": {}", self.kind)
+    }
 }
 
-fn parse_packet(bits: &str, cur: usize) -> (Packet, usize) {
-    let header = convert_bits_to_header(&bits[cur..cur+6]);
+impl Error for DecodeError {}
 
-    let version = header >> 3;
-    let packet_type = header & 0b111;
-    let (body, read_up_to) = convert_bits_to_body(packet_type, &bits, cur+6);
+fn convert_hex_to_bytes(str: &str) -> Result<Vec<u8>, DecodeError> {
+    let mut bytes = Vec::with_capacity(str.len() / 2 + 1);
+    for (i, pair) in str.as_bytes().chunks(2).enumerate() {
+        let high = convert_hex_digit(pair[0], i * 8)?;
+        let low = match pair.get(1) {
+            Some(c) => convert_hex_digit(*c, i * 8 + 4)?,
+            None => 0
+        };
+        bytes.push((high << 4) | low);
+    }
+    Ok(bytes)
+}
 
-    (Packet::from(version, packet_type, body), read_up_to)
+fn convert_hex_digit(c: u8, offset: usize) -> Result<u8, DecodeError> {
+    (c as char).to_digit(16)
+        .map(|d| d as u8)
+        .ok_or(DecodeError { offset, path: vec![], kind: DecodeErrorKind::InvalidHex(c as char) })
 }
 
-fn convert_bits_to_header(bits: &str) -> u8 {
-    u8::from_str_radix(&bits[..], 2).unwrap()
+fn convert_bytes_to_packet(bytes: &[u8]) -> Result<Packet, DecodeError> {
+    Decoder { reader: BitReader::new(bytes), path: vec![], offsets: None }.parse_packet()
 }
 
-fn convert_bits_to_literal_num(bits: &str) -> u64 {
-    u64::from_str_radix(&bits[..], 2).unwrap()
+struct Decoder<'a> {
+    reader: BitReader<'a>,
+    path: Vec<usize>,
+    /// When set, collects the bit offset of every packet in pre-order.
+    offsets: Option<Vec<usize>>
 }
 
-fn parse_literal(bits: &str, cur: usize) -> (PacketBody, usize) {
-    let mut bit_string = String::new();
-    let mut i = cur;
-    while i < bits.len() {
-        bit_string.push_str(&bits[i+1..i+5]);
+impl Decoder<'_> {
+    fn error(&self, offset: usize, kind: DecodeErrorKind) -> DecodeError {
+        DecodeError { offset, path: self.path.clone(), kind }
+    }
+
+    fn read(&mut self, n: usize) -> Result<u64, DecodeError> {
+        let offset = self.reader.position();
+        let available = self.reader.remaining();
+        self.reader.read(n)
+            .ok_or_else(|| self.error(offset, DecodeErrorKind::Truncated { needed: n, available }))
+    }
+
+    /// Reads the packet at the current position. Operators whose
+    /// sub-packets are still being read wait on a stack rather than in
+    /// nested calls, so deeply nested transmissions can't overflow it.
+    fn parse_packet(&mut self) -> Result<Packet, DecodeError> {
+        let mut open: Vec<Operator> = vec![];
+        loop {
+            let start = self.reader.position();
+            if let Some(offsets) = &mut self.offsets {
+                offsets.push(start);
+            }
+
+            let version = self.read(3)? as u8;
+            let packet_type = self.read(3)? as u8;
+            let mut finished = match packet_type {
+                4 => Some(Packet::from(version, packet_type, self.parse_literal()?)),
+                _ => {
+                    let length = self.parse_length()?;
+                    open.push(Operator { start, version, packet_type, length, packets: vec![] });
+                    None
+                }
+            };
+
+            // Hand each finished packet to the operator it belongs to, closing
+            // operators for as long as that finishes them too.
+            loop {
+                let Some(operator) = open.last_mut() else {
+                    return Ok(finished.expect("the outermost packet is finished once nothing is open"));
+                };
+                if let Some(packet) = finished.take() {
+                    self.path.pop();
+                    operator.packets.push(packet);
+                }
+                if !self.is_complete(operator)? {
+                    break;
+                }
+
+                let Operator { start, version, packet_type, packets, .. } = open.pop().expect("an operator is open");
+                let body = PacketBody::from(packet_type, packets)
+                    .ok_or_else(|| self.error(start + 3, DecodeErrorKind::UnknownType(packet_type)))?;
+                finished = Some(Packet::from(version, packet_type, body));
+            }
+
+            let operator = open.last().expect("an operator is open");
+            self.path.push(operator.packets.len());
+        }
+    }
 
-        let should_break = &bits[i..i+1] == "0";
-        i += 5;
+    fn parse_literal(&mut self) -> Result<PacketBody, DecodeError> {
+        let mut value: u64 = 0;
+        loop {
+            let group = self.reader.position();
+            let more = self.read(1)? == 1;
 
-        if should_break { break; }
-    }
+            if value >> 60 != 0 {
+                return Err(self.error(group, DecodeErrorKind::LiteralOverflow));
+            }
+            value = (value << 4) | self.read(4)?;
 
-    (PacketBody::Literal(convert_bits_to_literal_num(bit_string.as_str())), i)
-}
+            if !more { break; }
+        }
+
+        Ok(PacketBody::Literal(value))
+    }
 
-fn convert_bits_to_operations(bits: &str, packet_type: u8, cur: usize) -> (PacketBody, usize) {
-    let length_type = &bits[cur..cur+1];
-
-    if length_type == "0" {
-        let length = convert_bits_to_literal_num(&bits[cur+1..cur+16]) as usize;
-        let mut cur = cur+16; // we've read the length
-        let end =  cur + length;
-        let mut packets = vec![];
-        while cur < end {
-            let (packet, read) = parse_packet(bits, cur);
-            packets.push(packet);
-            cur = read;
+    fn parse_length(&mut self) -> Result<SubPackets, DecodeError> {
+        if self.read(1)? == 1 {
+            Ok(SubPackets::Count(self.read(11)? as usize))
+        } else {
+            let expected = self.read(15)? as usize;
+            Ok(SubPackets::Bits { start: self.reader.position(), expected })
         }
-        (PacketBody::from(packet_type, packets), cur)
-    } else {
-        let length = convert_bits_to_literal_num(&bits[cur+1..cur+12]) as usize;
-        let mut cur = cur+12;
-        let mut packets = vec![];
-        for _ in 0..length {
-            let (packet, read) = parse_packet(bits, cur);
-            packets.push(packet);
-            cur = read;
+    }
+
+    /// Whether the operator has all of its sub-packets, failing if they
+    /// overran the bits it set aside for them.
+    fn is_complete(&self, operator: &Operator) -> Result<bool, DecodeError> {
+        match operator.length {
+            SubPackets::Count(count) => Ok(operator.packets.len() >= count),
+            SubPackets::Bits { start, expected } => {
+                let actual = self.reader.position() - start;
+                if actual < expected {
+                    Ok(false)
+                } else if actual > expected {
+                    Err(self.error(start, DecodeErrorKind::LengthMismatch { expected, actual }))
+                } else {
+                    Ok(true)
+                }
+            }
         }
-        (PacketBody::from(packet_type, packets), cur)
     }
 }
 
-fn convert_bits_to_body(packet_type: u8, bits: &str, cur: usize) -> (PacketBody, usize) {
-    match packet_type {
-        4 => parse_literal(bits, cur),
-        _ => convert_bits_to_operations(bits, packet_type, cur)
-    }
+/// How an operator says where its sub-packets end.
+enum SubPackets {
+    Count(usize),
+    Bits { start: usize, expected: usize }
+}
+
+/// An operator whose sub-packets are still being read.
+struct Operator {
+    start: usize,
+    version: u8,
+    packet_type: u8,
+    length: SubPackets,
+    packets: Vec<Packet>
 }
 
 impl Puzzle<Input, Output> for Day16 {
     fn parse(&self, contents: Vec<String>) -> Result<Input, Box<dyn Error>> {
-        let bits = convert_hex_to_bits(contents[0].as_str()).unwrap();
-        let packet = convert_bit_string_to_packets(bits);
-        Ok(packet)
+        let line = contents.first().ok_or("empty transmission")?;
+        let bytes = convert_hex_to_bytes(line.trim())?;
+        Ok(convert_bytes_to_packet(&bytes)?)
     }
 
     fn calculate_part_1(&self, input: &Input) -> Output {
-        input.sum_of_versions()
+        Answer::Value(BigUint::from(input.sum_of_versions()))
     }
 
+    /// Evaluates in a `u64` where that's enough, and exactly where it isn't.
     fn calculate_part_2(&self, input: &Input) -> Output {
-        input.value()
+        let value = match input.checked_value() {
+            Ok(value) => Ok(BigUint::from(value)),
+            Err(_) => input.exact_value()
+        };
+        value.map_or_else(Answer::Invalid, Answer::Value)
     }
 }
 
@@ -202,8 +395,8 @@ mod test {
 
     #[test]
     fn test_example_1() {
-        let bits = convert_hex_to_bits("38006F45291200").unwrap();
-        let packet = convert_bit_string_to_packets(bits);
+        let bytes = convert_hex_to_bytes("38006F45291200").unwrap();
+        let packet = convert_bytes_to_packet(&bytes).unwrap();
         assert_eq!(packet, Packet::operation(0b001110,
                 vec![
                     Packet::literal(0b110100, 10),
@@ -214,8 +407,8 @@ mod test {
 
     #[test]
     fn test_example_2() {
-        let bits = convert_hex_to_bits("EE00D40C823060").unwrap();
-        let packet = convert_bit_string_to_packets(bits);
+        let bytes = convert_hex_to_bytes("EE00D40C823060").unwrap();
+        let packet = convert_bytes_to_packet(&bytes).unwrap();
         assert_eq!(packet, Packet::operation(0b111011,
                 vec![
                     Packet::literal(0b010100, 1),
@@ -227,102 +420,209 @@ mod test {
 
     #[test]
     fn test_example_3() {
-        let bits = convert_hex_to_bits("D2FE28").unwrap();
-        let packet = convert_bit_string_to_packets(bits);
+        let bytes = convert_hex_to_bytes("D2FE28").unwrap();
+        let packet = convert_bytes_to_packet(&bytes).unwrap();
         assert_eq!(packet, Packet::literal(0b110100, 2021));
     }
 
     #[test]
     fn test_sum_of_versions_1() {
-        let bits = convert_hex_to_bits("8A004A801A8002F478").unwrap();
-        let packet = convert_bit_string_to_packets(bits);
+        let bytes = convert_hex_to_bytes("8A004A801A8002F478").unwrap();
+        let packet = convert_bytes_to_packet(&bytes).unwrap();
         assert_eq!(16, packet.sum_of_versions())
     }
 
     #[test]
     fn test_sum_of_versions_2() {
-        let bits = convert_hex_to_bits("620080001611562C8802118E34").unwrap();
-        let packet = convert_bit_string_to_packets(bits);
+        let bytes = convert_hex_to_bytes("620080001611562C8802118E34").unwrap();
+        let packet = convert_bytes_to_packet(&bytes).unwrap();
         assert_eq!(12, packet.sum_of_versions())
     }
 
     #[test]
     fn test_sum_of_versions_3() {
-        let bits = convert_hex_to_bits("C0015000016115A2E0802F182340").unwrap();
-        let packet = convert_bit_string_to_packets(bits);
+        let bytes = convert_hex_to_bytes("C0015000016115A2E0802F182340").unwrap();
+        let packet = convert_bytes_to_packet(&bytes).unwrap();
         assert_eq!(23, packet.sum_of_versions())
     }
 
     #[test]
     fn test_sum_of_versions_4() {
-        let bits = convert_hex_to_bits("A0016C880162017C3686B18A3D4780").unwrap();
-        let packet = convert_bit_string_to_packets(bits);
+        let bytes = convert_hex_to_bytes("A0016C880162017C3686B18A3D4780").unwrap();
+        let packet = convert_bytes_to_packet(&bytes).unwrap();
         assert_eq!(31, packet.sum_of_versions())
     }
 
     #[test]
     fn test_process_example_sum() {
-        let bits = convert_hex_to_bits("C200B40A82").unwrap();
-        let packet = convert_bit_string_to_packets(bits);
-        assert_eq!(3, packet.value())
+        let bytes = convert_hex_to_bytes("C200B40A82").unwrap();
+        let packet = convert_bytes_to_packet(&bytes).unwrap();
+        assert_eq!(Ok(3), packet.checked_value())
     }
 
     #[test]
     fn test_process_example_product() {
-        let bits = convert_hex_to_bits("04005AC33890").unwrap();
-        let packet = convert_bit_string_to_packets(bits);
-        assert_eq!(54, packet.value())
+        let bytes = convert_hex_to_bytes("04005AC33890").unwrap();
+        let packet = convert_bytes_to_packet(&bytes).unwrap();
+        assert_eq!(Ok(54), packet.checked_value())
     }
 
     #[test]
     fn test_process_example_minimum() {
-        let bits = convert_hex_to_bits("880086C3E88112").unwrap();
-        let packet = convert_bit_string_to_packets(bits);
-        assert_eq!(7, packet.value())
+        let bytes = convert_hex_to_bytes("880086C3E88112").unwrap();
+        let packet = convert_bytes_to_packet(&bytes).unwrap();
+        assert_eq!(Ok(7), packet.checked_value())
     }
 
     #[test]
     fn test_process_example_maximum() {
-        let bits = convert_hex_to_bits("CE00C43D881120").unwrap();
-        let packet = convert_bit_string_to_packets(bits);
-        assert_eq!(9, packet.value())
+        let bytes = convert_hex_to_bytes("CE00C43D881120").unwrap();
+        let packet = convert_bytes_to_packet(&bytes).unwrap();
+        assert_eq!(Ok(9), packet.checked_value())
     }
 
     #[test]
     fn test_process_example_less_than() {
-        let bits = convert_hex_to_bits("D8005AC2A8F0").unwrap();
-        let packet = convert_bit_string_to_packets(bits);
-        assert_eq!(1, packet.value())
+        let bytes = convert_hex_to_bytes("D8005AC2A8F0").unwrap();
+        let packet = convert_bytes_to_packet(&bytes).unwrap();
+        assert_eq!(Ok(1), packet.checked_value())
     }
 
     #[test]
     fn test_process_example_greater_than() {
-        let bits = convert_hex_to_bits("F600BC2D8F").unwrap();
-        let packet = convert_bit_string_to_packets(bits);
-        assert_eq!(0, packet.value())
+        let bytes = convert_hex_to_bytes("F600BC2D8F").unwrap();
+        let packet = convert_bytes_to_packet(&bytes).unwrap();
+        assert_eq!(Ok(0), packet.checked_value())
     }
 
     #[test]
     fn test_process_example_equal() {
-        let bits = convert_hex_to_bits("9C005AC2F8F0").unwrap();
-        let packet = convert_bit_string_to_packets(bits);
-        assert_eq!(0, packet.value())
+        let bytes = convert_hex_to_bytes("9C005AC2F8F0").unwrap();
+        let packet = convert_bytes_to_packet(&bytes).unwrap();
+        assert_eq!(Ok(0), packet.checked_value())
     }
 
     #[test]
     fn test_process_example_equal_combine() {
-        let bits = convert_hex_to_bits("9C0141080250320F1802104A08").unwrap();
-        let packet = convert_bit_string_to_packets(bits);
-        assert_eq!(1, packet.value())
+        let bytes = convert_hex_to_bytes("9C0141080250320F1802104A08").unwrap();
+        let packet = convert_bytes_to_packet(&bytes).unwrap();
+        assert_eq!(Ok(1), packet.checked_value())
     }
 
     #[test]
     fn test_conversion_1() {
-        assert_eq!(convert_hex_to_bits("D2FE28").unwrap(), "110100101111111000101000");
+        assert_eq!(convert_hex_to_bytes("D2FE28").unwrap(), vec![0xD2, 0xFE, 0x28]);
     }
 
     #[test]
     fn test_conversion_2() {
-        assert_eq!(convert_hex_to_bits("38006F45291200").unwrap(), "00111000000000000110111101000101001010010001001000000000");
+        assert_eq!(convert_hex_to_bytes("38006F45291200").unwrap(), vec![0x38, 0x00, 0x6F, 0x45, 0x29, 0x12, 0x00]);
+    }
+
+    #[test]
+    fn test_conversion_odd_length() {
+        assert_eq!(convert_hex_to_bytes("D2F").unwrap(), vec![0xD2, 0xF0]);
+    }
+
+    fn convert_bit_string_to_bytes(bits: &str) -> Vec<u8> {
+        bits.as_bytes().chunks(8)
+            .map(|chunk| chunk.iter().enumerate()
+                .fold(0, |byte, (i, b)| byte | ((b - b'0') << (7 - i))))
+            .collect()
+    }
+
+    #[test]
+    fn test_error_truncated_literal() {
+        let bytes = convert_hex_to_bytes("D2FE").unwrap();
+        let error = convert_bytes_to_packet(&bytes).unwrap_err();
+        assert_eq!(DecodeError { offset: 16, path: vec![], kind: DecodeErrorKind::Truncated { needed: 1, available: 0 } }, error);
+    }
+
+    #[test]
+    fn test_error_truncated_sub_packet() {
+        let bytes = convert_hex_to_bytes("38006F452912").unwrap();
+        let error = convert_bytes_to_packet(&bytes).unwrap_err();
+        assert_eq!(DecodeError { offset: 45, path: vec![1], kind: DecodeErrorKind::Truncated { needed: 4, available: 3 } }, error);
+        assert_eq!("bit 45 at root > sub[1]: truncated, needed 4 bits but only 3 left", error.to_string());
+    }
+
+    #[test]
+    fn test_error_literal_overflow() {
+        let bits = format!("000100{}{}", "11111".repeat(16), "00001");
+        let error = convert_bytes_to_packet(&convert_bit_string_to_bytes(&bits)).unwrap_err();
+        assert_eq!(DecodeError { offset: 86, path: vec![], kind: DecodeErrorKind::LiteralOverflow }, error);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_literal_of_exactly_64_bits() {
+        let bits = format!("000100{}{}", "11111".repeat(15), "01111");
+        let packet = convert_bytes_to_packet(&convert_bit_string_to_bytes(&bits)).unwrap();
+        assert_eq!(Ok(u64::MAX), packet.checked_value());
+    }
+
+    #[test]
+    fn test_error_length_mismatch() {
+        // a sum claiming 10 bits of sub-packets that holds an 11 bit literal
+        let bits = "0000000000000000001010000100000010000000";
+        let error = convert_bytes_to_packet(&convert_bit_string_to_bytes(bits)).unwrap_err();
+        assert_eq!(DecodeError { offset: 22, path: vec![], kind: DecodeErrorKind::LengthMismatch { expected: 10, actual: 11 } }, error);
+    }
+
+    #[test]
+    fn test_error_nested_path() {
+        // sum > [lit, lit, sum > [truncated]]
+        let bits = "0000001000000000110001000000100010000001000000100000000001000100";
+        let error = convert_bytes_to_packet(&convert_bit_string_to_bytes(bits)).unwrap_err();
+        assert_eq!(vec![2, 0], error.path);
+        assert!(error.to_string().starts_with("bit 64 at root > sub[2] > sub[0]: truncated"));
+    }
+
+    #[test]
+    fn test_error_invalid_hex() {
+        let error = convert_hex_to_bytes("D2G8").unwrap_err();
+        assert_eq!(DecodeError { offset: 8, path: vec![], kind: DecodeErrorKind::InvalidHex('G') }, error);
+    }
+
+    #[test]
+    fn test_unknown_operator_type() {
+        assert_eq!(None, PacketBody::from(4, vec![]));
+    }
+
+    #[test]
+    fn test_deeply_nested_transmission() {
+        // 100,000 sums, each holding just the next, around a literal 5
+        let mut writer = crate::bits::BitWriter::new();
+        for _ in 0..100_000 {
+            writer.write(0b001000, 6);
+            writer.write(1, 1);
+            writer.write(1, 11);
+        }
+        writer.write(0b001100, 6);
+        writer.write(0b00101, 5);
+        let bytes = writer.into_bytes();
+
+        let packet = convert_bytes_to_packet(&bytes).unwrap();
+        assert_eq!(100_001, packet.depth());
+        assert_eq!(100_001, packet.sum_of_versions());
+        assert_eq!(Ok(5), packet.checked_value());
+
+        let error = convert_bytes_to_packet(&bytes[..bytes.len() - 2]).unwrap_err();
+        assert_eq!(100_000, error.path.len());
+    }
+
+    #[test]
+    fn test_part_two_past_u64() -> Result<(), Box<dyn Error>> {
+        let answers = |hex: &str| -> Result<(String, String), Box<dyn Error>> {
+            let packet = Day16.parse(vec![hex.to_string()])?;
+            Ok((format!("{:?}", Day16.calculate_part_1(&packet)), format!("{:?}", Day16.calculate_part_2(&packet))))
+        };
+
+        // a product of 2^40 and 2^40
+        assert_eq!(("13".to_string(), "1208925819614629174706176".to_string()), answers("2401EB48C210842108401A46108421084200")?);
+
+        assert_eq!(("0".to_string(), "3".to_string()), answers(&crate::asm::assemble("(sum (lit 1) (lit 2))")?)?);
+        let (_, invalid) = answers(&crate::asm::assemble("(sum (lit 1) (eq (lit 2)))")?)?;
+        assert_eq!("root > sub[1]: eq expects exactly 2 sub-packets but has 1", invalid);
+        Ok(())
+    }
+}
@@ -0,0 +1,219 @@
+use crate::Packet;
+
+/// Yields each packet before its sub-packets, left to right.
+pub(crate) struct PreOrder<'a> {
+    stack: Vec<&'a Packet>
+}
+
+impl<'a> Iterator for PreOrder<'a> {
+    type Item = &'a Packet;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let packet = self.stack.pop()?;
+        self.stack.extend(packet.children().iter().rev());
+        Some(packet)
+    }
+}
+
+/// Walks each packet after all of its sub-packets, left to right, along with
+/// its path from the root: the index of each sub-packet stepped into. Not an
+/// `Iterator`, as the path it hands out is borrowed from the walk.
+pub(crate) struct PostOrderPaths<'a> {
+    stack: Vec<(&'a Packet, usize)>,
+    path: Vec<usize>,
+    /// Whether the last packet handed out still has its index on `path`.
+    yielded: bool
+}
+
+impl<'a> PostOrderPaths<'a> {
+    pub fn next(&mut self) -> Option<(&'a Packet, &[usize])> {
+        if self.yielded {
+            self.path.pop();
+            self.yielded = false;
+        }
+
+        loop {
+            let (packet, next_child) = self.stack.last_mut()?;
+            let packet: &'a Packet = packet;
+            match packet.children().get(*next_child) {
+                Some(child) => {
+                    self.path.push(*next_child);
+                    *next_child += 1;
+                    self.stack.push((child, 0));
+                },
+                None => {
+                    self.stack.pop();
+                    self.yielded = true;
+                    return Some((packet, &self.path));
+                }
+            }
+        }
+    }
+}
+
+/// Yields each packet after all of its sub-packets, left to right.
+pub(crate) struct PostOrder<'a> {
+    walk: PostOrderPaths<'a>
+}
+
+impl<'a> Iterator for PostOrder<'a> {
+    type Item = &'a Packet;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.walk.next().map(|(packet, _)| packet)
+    }
+}
+
+/// Frees the tree from a stack, as dropping each packet's sub-packets in turn
+/// would recurse once per level.
+impl Drop for Packet {
+    fn drop(&mut self) {
+        let mut stack = self.body.take_children();
+        while let Some(mut packet) = stack.pop() {
+            stack.append(&mut packet.body.take_children());
+        }
+    }
+}
+
+impl Packet {
+    pub fn children(&self) -> &[Packet] {
+        self.body.children()
+    }
+
+    pub fn pre_order(&self) -> PreOrder<'_> {
+        PreOrder { stack: vec![self] }
+    }
+
+    pub fn post_order(&self) -> PostOrder<'_> {
+        PostOrder { walk: self.post_order_paths() }
+    }
+
+    pub fn post_order_paths(&self) -> PostOrderPaths<'_> {
+        PostOrderPaths { stack: vec![(self, 0)], path: vec![], yielded: false }
+    }
+
+    /// Combines the tree bottom up: `f` gets each packet along with the
+    /// results already computed for its sub-packets.
+    pub fn fold<T>(&self, mut f: impl FnMut(&Packet, Vec<T>) -> T) -> T {
+        let mut results = vec![];
+        for packet in self.post_order() {
+            let children = results.split_off(results.len() - packet.children().len());
+            results.push(f(packet, children));
+        }
+        results.pop().expect("the root is always folded last")
+    }
+
+    /// Like `fold`, but `f` also gets the packet's path from the root and may
+    /// stop the fold by returning an error.
+    pub fn try_fold<T, E>(&self, mut f: impl FnMut(&Packet, &[usize], Vec<T>) -> Result<T, E>) -> Result<T, E> {
+        let mut walk = self.post_order_paths();
+        let mut results = vec![];
+        while let Some((packet, path)) = walk.next() {
+            let children = results.split_off(results.len() - packet.children().len());
+            results.push(f(packet, path, children)?);
+        }
+        Ok(results.pop().expect("the root is always folded last"))
+    }
+
+    pub fn depth(&self) -> usize {
+        self.fold(|_, depths| 1 + depths.into_iter().max().unwrap_or_default())
+    }
+
+    /// How many packets of each type the tree holds, indexed by packet type.
+    pub fn type_counts(&self) -> [usize; 8] {
+        let mut counts = [0; 8];
+        for packet in self.pre_order() {
+            counts[packet.body.type_id() as usize] += 1;
+        }
+        counts
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::asm::compile;
+    use super::*;
+
+    fn names<'a>(packets: impl Iterator<Item = &'a Packet>) -> Vec<String> {
+        packets.map(|p| match p.body {
+            crate::PacketBody::Literal(v) => v.to_string(),
+            ref body => body.name().to_string()
+        }).collect()
+    }
+
+    #[test]
+    fn test_children() {
+        let packet = compile("(sum (lit 1) (product (lit 2)) (lit 3))").unwrap();
+        assert_eq!(3, packet.children().len());
+        assert!(packet.children()[0].children().is_empty());
+    }
+
+    #[test]
+    fn test_pre_order() {
+        let packet = compile("(sum (lit 1) (product (lit 2) (lit 3)) (lit 4))").unwrap();
+        assert_eq!(vec!["sum", "1", "product", "2", "3", "4"], names(packet.pre_order()));
+    }
+
+    #[test]
+    fn test_post_order() {
+        let packet = compile("(sum (lit 1) (product (lit 2) (lit 3)) (lit 4))").unwrap();
+        assert_eq!(vec!["1", "2", "3", "product", "4", "sum"], names(packet.post_order()));
+    }
+
+    #[test]
+    fn test_post_order_paths() {
+        let packet = compile("(sum (lit 1) (product (lit 2) (lit 3)) (lit 4))").unwrap();
+        let mut walk = packet.post_order_paths();
+        let mut paths = vec![];
+        while let Some((_, path)) = walk.next() {
+            paths.push(path.to_vec());
+        }
+        assert_eq!(vec![vec![0], vec![1, 0], vec![1, 1], vec![1], vec![2], vec![]], paths);
+        assert!(walk.next().is_none());
+    }
+
+    #[test]
+    fn test_fold_counts_leaves() {
+        let packet = compile("(sum (lit 1) (product (lit 2) (lit 3) (min)) (lit 4))").unwrap();
+        let leaves = packet.fold(|p, counts: Vec<usize>| if p.children().is_empty() { 1 } else { counts.iter().sum() });
+        assert_eq!(5, leaves);
+    }
+
+    #[test]
+    fn test_try_fold_reports_path() {
+        let packet = compile("(sum (lit 1) (product (lit 2) (lit 0)))").unwrap();
+        let result = packet.try_fold(|p, path, _: Vec<()>| match p.body {
+            crate::PacketBody::Literal(0) => Err(path.to_vec()),
+            _ => Ok(())
+        });
+        assert_eq!(Err(vec![1, 1]), result);
+    }
+
+    #[test]
+    fn test_depth_and_type_counts() {
+        let packet = compile("(sum (lit 1) (product (lit 2) (max (lit 3))) (lit 4))").unwrap();
+        assert_eq!(4, packet.depth());
+        assert_eq!([1, 1, 0, 1, 4, 0, 0, 0], packet.type_counts());
+    }
+
+    #[test]
+    fn test_deep_tree_iterators_do_not_recurse() {
+        let src = format!("{}(lit 1){}", "(sum ".repeat(1000), ")".repeat(1000));
+        let packet = compile(&src).unwrap();
+        assert_eq!(1001, packet.pre_order().count());
+        assert_eq!(1001, packet.post_order().count());
+        assert_eq!(Ok(1), packet.checked_value());
+    }
+
+    #[test]
+    fn test_deep_try_fold_reports_path() {
+        let src = format!("{}(min){}", "(sum ".repeat(100_000), ")".repeat(100_000));
+        let packet = compile(&src).unwrap();
+        let result = packet.try_fold(|p, path, _: Vec<()>| match p.body {
+            crate::PacketBody::Minimum(_) => Err(path.len()),
+            _ => Ok(())
+        });
+        assert_eq!(Err(100_000), result);
+        assert!(compile(&src.replace("(min)", "(lit 0)")).unwrap().exact_value().unwrap().is_zero());
+    }
+}
@@ -0,0 +1,200 @@
+use crate::rules::Rules;
+
+/// A snailfish number as its regular numbers in reading order, each with the
+/// number of pairs enclosing it. `[[1,2],3]` is `[(1, 2), (2, 2), (3, 1)]`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct FlatNumber {
+    entries: Vec<(i64, usize)>
+}
+
+impl FlatNumber {
+    pub fn parse(s: &str) -> Self {
+        let mut entries = vec![];
+        let mut depth = 0;
+        let mut number = None;
+
+        for c in s.chars() {
+            if let Some(digit) = c.to_digit(10) {
+                number = Some(number.unwrap_or(0) * 10 + digit as i64);
+                continue;
+            }
+            if let Some(n) = number.take() {
+                entries.push((n, depth));
+            }
+            match c {
+                '[' => depth += 1,
+                ']' => depth -= 1,
+                _ => {}
+            }
+        }
+
+        FlatNumber { entries }
+    }
+
+    /// Adds and reduces, leaving both operands untouched.
+    pub fn add(&self, other: &FlatNumber, rules: &Rules) -> FlatNumber {
+        let mut entries = Vec::with_capacity(self.entries.len() + other.entries.len());
+        entries.extend(self.entries.iter().chain(&other.entries).map(|(v, d)| (*v, d + 1)));
+
+        let mut sum = FlatNumber { entries };
+        sum.reduce(rules);
+        sum
+    }
+
+    pub fn reduce(&mut self, rules: &Rules) {
+        while self.explode(rules) || self.split(rules) {}
+    }
+
+    /// Explodes the leftmost pair of regular numbers nested inside at least
+    /// the explode depth of pairs. The first deep enough number followed by
+    /// one at the same depth always starts such a pair: anything to its left
+    /// in the same pair would hold a deep enough pair of its own.
+    fn explode(&mut self, rules: &Rules) -> bool {
+        let i = match self.entries.windows(2).position(|w| w[0].1 > rules.explode_depth() && w[0].1 == w[1].1) {
+            Some(i) => i,
+            None => return false
+        };
+
+        let (left, depth) = self.entries[i];
+        let (right, _) = self.entries[i + 1];
+        if i > 0 {
+            self.entries[i - 1].0 += left;
+        }
+        if let Some(next) = self.entries.get_mut(i + 2) {
+            next.0 += right;
+        }
+
+        self.entries[i] = (0, depth - 1);
+        self.entries.remove(i + 1);
+        true
+    }
+
+    /// Splits the leftmost regular number past the threshold.
+    fn split(&mut self, rules: &Rules) -> bool {
+        let i = match self.entries.iter().position(|(v, _)| *v > rules.split_above()) {
+            Some(i) => i,
+            None => return false
+        };
+
+        let (value, depth) = self.entries[i];
+        self.entries[i] = (value / 2, depth + 1);
+        self.entries.insert(i + 1, ((value + 1) / 2, depth + 1));
+        true
+    }
+
+    pub fn magnitude(&self, rules: &Rules) -> i64 {
+        let mut stack: Vec<(i64, usize)> = vec![];
+        for &entry in &self.entries {
+            stack.push(entry);
+            while stack.len() >= 2 && stack[stack.len() - 1].1 == stack[stack.len() - 2].1 {
+                let (right, depth) = stack.pop().unwrap();
+                let (left, _) = stack.pop().unwrap();
+                stack.push((rules.left_weight() * left + rules.right_weight() * right, depth - 1));
+            }
+        }
+
+        stack.first().map_or(0, |(v, _)| *v)
+    }
+}
+
+pub fn calculate_part_2(input: &[FlatNumber], rules: &Rules) -> i64 {
+    let mut amount = 0;
+    for (i, left) in input.iter().enumerate() {
+        for (j, right) in input.iter().enumerate() {
+            if i != j {
+                amount = amount.max(left.add(right, rules).magnitude(rules));
+            }
+        }
+    }
+
+    amount
+}
+
+#[cfg(test)]
+mod test {
+    use std::error::Error;
+    use aoc_utils::read_file;
+    use crate::{ChildNode, NodeKind, add_and_reduce, calc_magnitude};
+    use crate::number::SnailfishNumber;
+    use super::*;
+
+    fn from_tree(node: &ChildNode) -> FlatNumber {
+        fn walk(node: &ChildNode, depth: usize, entries: &mut Vec<(i64, usize)>) {
+            if let Some(n) = node {
+                match n.kind {
+                    NodeKind::Value(i) => entries.push((i, depth)),
+                    NodeKind::Branch => {
+                        walk(&n.left, depth + 1, entries);
+                        walk(&n.right, depth + 1, entries);
+                    }
+                }
+            }
+        }
+
+        let mut entries = vec![];
+        walk(node, 0, &mut entries);
+        FlatNumber { entries }
+    }
+
+    #[test]
+    fn test_parse() {
+        let flat = FlatNumber::parse("[[1,2],13]");
+        assert_eq!(vec![(1, 2), (2, 2), (13, 1)], flat.entries);
+        assert_eq!(from_tree(&"[[1,2],13]".parse::<SnailfishNumber>().unwrap().0), flat);
+    }
+
+    #[test]
+    fn test_explode() {
+        let mut flat = FlatNumber::parse("[[6,[5,[4,[3,2]]]],1]");
+        assert!(flat.explode(&Rules::default()));
+        assert_eq!(FlatNumber::parse("[[6,[5,[7,0]]],3]"), flat);
+    }
+
+    #[test]
+    fn test_split() {
+        let mut flat = FlatNumber::parse("[11,1]");
+        assert!(flat.split(&Rules::default()));
+        assert_eq!(FlatNumber::parse("[[5,6],1]"), flat);
+    }
+
+    #[test]
+    fn test_magnitude() {
+        assert_eq!(143, FlatNumber::parse("[[1,2],[[3,4],5]]").magnitude(&Rules::default()));
+        assert_eq!(3488, FlatNumber::parse("[[[[8,7],[7,7]],[[8,6],[7,7]]],[[[0,7],[6,6]],[8,7]]]").magnitude(&Rules::default()));
+    }
+
+    #[test]
+    fn test_matches_tree_on_every_pair() -> Result<(), Box<dyn Error>> {
+        let lines = read_file("./inputs/day_18_test.in")?;
+        for rules in [Rules::default(), Rules::new(3, 12, 2, 5)?, Rules::new(5, 5, 1, 1)?, Rules::new(2, 20, 7, 1)?] {
+            for left in &lines {
+                for right in &lines {
+                    let tree = add_and_reduce(left.parse::<SnailfishNumber>()?.0, right.parse::<SnailfishNumber>()?.0, &rules);
+                    let flat = FlatNumber::parse(left).add(&FlatNumber::parse(right), &rules);
+
+                    assert_eq!(from_tree(&tree), flat, "{:?}", rules);
+                    assert_eq!(calc_magnitude(&tree, &rules), flat.magnitude(&rules));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_explode_skips_unreduced_pairs() {
+        // [1,[2,3]] is nested deep enough, but only [2,3] is a pair of
+        // regular numbers.
+        let mut flat = FlatNumber::parse("[[[1,[2,3]],4],5]");
+        assert!(flat.explode(&Rules::new(1, 9, 3, 2).unwrap()));
+        assert_eq!(FlatNumber::parse("[[[3,0],7],5]"), flat);
+    }
+
+    #[test]
+    fn test_calculate_part_2() -> Result<(), Box<dyn Error>> {
+        let input = read_file("./inputs/day_18_test.in")?.iter()
+            .map(|s| FlatNumber::parse(s))
+            .collect::<Vec<_>>();
+        assert_eq!(3993, calculate_part_2(&input, &Rules::default()));
+        Ok(())
+    }
+}
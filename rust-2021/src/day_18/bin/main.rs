@@ -1,15 +1,81 @@
-use std::cmp::max;
+use std::env;
 use std::error::Error;
-use aoc_utils::{Puzzle, run_all};
+use std::time::Instant;
+use aoc_utils::{Puzzle, read_file, run_all};
+use aoc_utils::parallel::{default_threads, map_pairs};
+use crate::flat::FlatNumber;
+use crate::number::SnailfishNumber;
+use crate::rules::Rules;
+use crate::trace::{Action, Side};
 use crate::NodeKind::{Branch, Value};
 
+mod flat;
+mod number;
+mod rules;
+mod trace;
+
 fn main() -> Result<(), Box<dyn Error>> {
-    run_all("./rust-2021/inputs/day_18.in", Box::new(Day18))
+    let mut args = env::args().collect::<Vec<_>>();
+    let rules = match args.iter().position(|a| a == "--rules") {
+        Some(i) => {
+            let spec = args.get(i + 1).ok_or("usage: --rules <explode depth>,<split above>,<left weight>,<right weight>")?.clone();
+            args.drain(i..i + 2);
+            parse_rules(&spec)?
+        },
+        None => Rules::default()
+    };
+
+    match args.get(1).map(|s| s.as_str()) {
+        Some("bench") => return bench("./rust-2021/inputs/day_18.in", &rules),
+        Some("trace") if args.len() == 4 => {
+            let left = SnailfishNumber::parse_with(&args[2], &rules)?;
+            let (sum, trace) = left.add_traced(SnailfishNumber::parse_with(&args[3], &rules)?, &rules);
+            println!("{}", trace);
+            println!("magnitude: {}", sum.magnitude_with(&rules));
+            return Ok(());
+        },
+        _ => {}
+    }
+    run_all("./rust-2021/inputs/day_18.in", Box::new(Day18(rules)))
+}
+
+fn parse_rules(spec: &str) -> Result<Rules, Box<dyn Error>> {
+    let values = spec.split(',').collect::<Vec<_>>();
+    if values.len() != 4 {
+        return Err(format!("expected four comma separated rules in {:?}", spec).into());
+    }
+    Ok(Rules::new(values[0].parse()?, values[1].parse()?, values[2].parse()?, values[3].parse()?)?)
+}
+
+/// Times part 2 on the tree representation, single and multi-threaded, and on
+/// the flat one.
+fn bench(path: &str, rules: &Rules) -> Result<(), Box<dyn Error>> {
+    let contents = read_file(path)?;
+
+    let trees = Day18(*rules).parse(contents.clone())?;
+    for threads in [1, default_threads()] {
+        let start = Instant::now();
+        let (amount, (i, j)) = largest_pair(&trees, threads, rules).unwrap_or_default();
+        println!("tree on {} thread(s): {} from lines {} and {} in {:?}", threads, amount, i + 1, j + 1, start.elapsed());
+    }
+
+    let flats = contents.iter()
+        .map(|s| {
+            let mut flat = FlatNumber::parse(s);
+            flat.reduce(rules);
+            flat
+        })
+        .collect::<Vec<_>>();
+    let start = Instant::now();
+    let amount = flat::calculate_part_2(&flats, rules);
+    println!("flat: {} in {:?}", amount, start.elapsed());
+
+    Ok(())
 }
 
-struct Day18;
+struct Day18(Rules);
 
-type Input = Vec<ChildNode>;
+type Input = Vec<SnailfishNumber>;
 type Output = i64;
 
 #[derive(Debug, PartialEq, Clone)]
@@ -59,38 +125,15 @@ impl Node {
     }
 }
 
-fn parse_str_to_tree(s: &str, cur: usize) -> (Option<Box<Node>>, usize) {
-    let cur_read_str = &s[cur..cur+1];
-    return if cur_read_str == "[" {
-        let (left, cur) = parse_str_to_tree(s, cur + 1);
-        let (right, cur) = parse_str_to_tree(s, cur);
-        (Node::new_node(left, right), cur)
-    } else if cur_read_str == "," || cur_read_str == "]" {
-        let (node, cur) = parse_str_to_tree(s, cur + 1);
-        (node, cur)
-    } else {
-        let mut peek = cur;
-        for i in 0..s.len() {
-            let peek_str = &s[peek..peek+1];
-            if peek_str == "[" || peek_str == "," || peek_str == "]" {
-                break;
-            }
-            peek += 1;
-        }
-        let value = (&s[cur..peek]).parse::<i64>().unwrap();
-        (Node::new_child_value(value), peek)
-    }
-}
-
-fn calc_magnitude(node: ChildNode) -> i64 {
+fn calc_magnitude(node: &ChildNode, rules: &Rules) -> i64 {
     match node {
         None => 0,
         Some(n) => {
             match n.kind {
                 Value(i) => i,
                 Branch => {
-                    let left = 3 * calc_magnitude(n.left);
-                    let right = 2 * calc_magnitude(n.right);
+                    let left = rules.left_weight() * calc_magnitude(&n.left, rules);
+                    let right = rules.right_weight() * calc_magnitude(&n.right, rules);
 
                     left + right
                 }
@@ -103,15 +146,19 @@ fn add(left: ChildNode, right: ChildNode) -> ChildNode {
     Node::new_node(left, right)
 }
 
-fn add_and_reduce(left: ChildNode, right: ChildNode) -> ChildNode {
-    let mut initial = add(left, right);
+fn add_and_reduce(left: ChildNode, right: ChildNode, rules: &Rules) -> ChildNode {
+    reduce(add(left, right), rules)
+}
+
+fn reduce(node: ChildNode, rules: &Rules) -> ChildNode {
+    let mut initial = node;
 
     loop {
-        let try_process = process(initial);
+        let try_process = process(initial, rules);
 
         initial = try_process.0;
 
-        if try_process.1 == false {
+        if try_process.1.is_none() {
             break;
         }
     }
@@ -119,38 +166,48 @@ fn add_and_reduce(left: ChildNode, right: ChildNode) -> ChildNode {
     initial
 }
 
-fn process(node: ChildNode) -> (ChildNode, bool) {
-    let after_explode = try_explode(node, 0);
+/// Applies the first explosion, or failing that the first split, and returns
+/// which action it took, if any.
+fn process(node: ChildNode, rules: &Rules) -> (ChildNode, Option<Action>) {
+    let mut action = None;
+    let after_explode = try_explode(node, rules, &mut vec![], &mut action);
 
-    if let Some(_) = after_explode.1 {
-        return (after_explode.0, true);
+    if after_explode.1.is_some() {
+        (after_explode.0, action)
     } else {
-        try_split(after_explode.0)
+        let after_split = try_split(after_explode.0, rules, &mut vec![], &mut action);
+        (after_split.0, action)
     }
 }
 
-fn try_split(node: ChildNode) -> (ChildNode, bool) {
+fn try_split(node: ChildNode, rules: &Rules, path: &mut Vec<Side>, action: &mut Option<Action>) -> (ChildNode, bool) {
     let mut splitted = false;
     (node.and_then(|n| {
         match n.kind {
             Branch => {
-                let try_left = try_split(n.left);
+                path.push(Side::Left);
+                let try_left = try_split(n.left, rules, path, action);
+                path.pop();
 
                 if try_left.1 {
                     splitted = true;
                     return Node::new_node(try_left.0, n.right)
                 }
 
-                let try_right = try_split(n.right);
+                path.push(Side::Right);
+                let try_right = try_split(n.right, rules, path, action);
+                path.pop();
+
                 splitted = try_right.1;
                 Node::new_node(try_left.0, try_right.0)
             },
-            Value(i) if i > 9 => {
+            Value(i) if i > rules.split_above() => {
                 let divide = i as f64 / 2.0;
                 let left = divide.floor() as i64;
                 let right = divide.ceil() as i64;
 
                 splitted = true;
+                *action = Some(Action::Split { path: path.clone(), value: i });
 
                 Node::new_node(Node::new_child_value(left), Node::new_child_value(right))
             },
@@ -161,21 +218,28 @@ fn try_split(node: ChildNode) -> (ChildNode, bool) {
 
 type Explosion = Option<(Option<i64>, Option<i64>)>;
 
-fn try_explode(node: ChildNode, level: usize) -> (ChildNode, Explosion) {
+fn try_explode(node: ChildNode, rules: &Rules, path: &mut Vec<Side>, action: &mut Option<Action>) -> (ChildNode, Explosion) {
     let mut explosion = None;
     (node.and_then(|mut n| {
         match n.kind {
             Value(_) => Some(n),
             Branch => {
-                if level == 4 && !n.more_branches() {
+                if path.len() >= rules.explode_depth() && !n.more_branches() {
                     let left = n.left.take().map(|n| n.value());
                     let right = n.right.take().map(|n| n.value());
 
                     explosion = Some((left, right));
+                    *action = Some(Action::Explode {
+                        path: path.clone(),
+                        left: left.unwrap_or_default(),
+                        right: right.unwrap_or_default()
+                    });
 
                     Node::new_child_value(0)
                 } else {
-                    let process_left = try_explode(n.left, level + 1);
+                    path.push(Side::Left);
+                    let process_left = try_explode(n.left, rules, path, action);
+                    path.pop();
                     let left = process_left.0;
                     let left_explosion = process_left.1;
 
@@ -186,7 +250,9 @@ fn try_explode(node: ChildNode, level: usize) -> (ChildNode, Explosion) {
                         return Node::new_node(left, right);
                     }
 
-                    let process_right = try_explode(n.right, level + 1);
+                    path.push(Side::Right);
+                    let process_right = try_explode(n.right, rules, path, action);
+                    path.pop();
                     let right = process_right.0;
                     let right_explosion = process_right.1;
 
@@ -197,7 +263,7 @@ fn try_explode(node: ChildNode, level: usize) -> (ChildNode, Explosion) {
                         return Node::new_node(left, right);
                     }
 
-                    return Node::new_node(left, right);
+                    Node::new_node(left, right)
                 }
             }
         }
@@ -241,54 +307,47 @@ fn find_and_add_most_left(node: ChildNode, explosion: Option<i64>) -> ChildNode
 impl Puzzle<Input, Output> for Day18 {
     fn parse(&self, contents: Vec<String>) -> Result<Input, Box<dyn Error>> {
         Ok(contents.iter()
-            .map(|s| parse_str_to_tree(&s[..], 0).0)
-               .collect())
+            .map(|s| SnailfishNumber::parse_with(s, &self.0))
+            .collect::<Result<_, _>>()?)
     }
 
     fn calculate_part_1(&self, input: &Input) -> Output {
-        let mut input = input.clone();
-        let mut initial = input[0].take();
-
-        for i in 1..input.len() {
-            let right = input[i].take();
-            initial = add_and_reduce(initial, right);
-        }
-
-        calc_magnitude(initial)
+        input.iter().cloned()
+            .reduce(|sum, n| sum.add_with(n, &self.0))
+            .map_or(0, |sum| sum.magnitude_with(&self.0))
     }
 
     fn calculate_part_2(&self, input: &Input) -> Output {
-        let mut amount = 0;
-        for i in 0..input.len() {
-            for j in i+1..input.len() {
-                let left = input[i].clone();
-                let right = input[j].clone();
-
-                let reduced = add_and_reduce(left.clone(), right.clone());
-                let left_amount = calc_magnitude(reduced);
-
-                let reduced = add_and_reduce(right.clone(), left.clone());
-                let right_amount = calc_magnitude(reduced);
-                amount = max(amount, max(left_amount, right_amount));
-            }
-        }
-
-        amount
+        largest_pair(input, default_threads(), &self.0).map_or(0, |(amount, _)| amount)
     }
 }
 
+/// The largest magnitude from adding two different numbers, with the indices
+/// of the pair that gave it. Ties go to the earliest pair, so the answer
+/// doesn't depend on `threads`.
+fn largest_pair(input: &[SnailfishNumber], threads: usize, rules: &Rules) -> Option<(Output, (usize, usize))> {
+    map_pairs(input.len(), threads, |i, j| input[i].clone().add_with(input[j].clone(), rules).magnitude_with(rules))
+        .into_iter()
+        .max_by(|(a, amount_a), (b, amount_b)| amount_a.cmp(amount_b).then(b.cmp(a)))
+        .map(|(pair, amount)| (amount, pair))
+}
+
 #[cfg(test)]
 mod test {
     use aoc_utils::{run_part_one, run_part_two};
     use super::*;
 
+    fn parse(s: &str) -> ChildNode {
+        s.parse::<SnailfishNumber>().unwrap().0
+    }
+
     #[test]
     fn test_parse_simple() {
         let expected = Node::new_node(
             Node::new_child_value(1),
             Node::new_child_value(2)
         );
-        assert_eq!(expected, parse_str_to_tree("[1,2]", 0).0);
+        assert_eq!(expected, parse("[1,2]"));
     }
 
     #[test]
@@ -297,7 +356,7 @@ mod test {
             Node::new_node(Node::new_child_value(1), Node::new_child_value(2)),
             Node::new_child_value(3)
         );
-        assert_eq!(expected, parse_str_to_tree("[[1,2],3]", 0).0);
+        assert_eq!(expected, parse("[[1,2],3]"));
     }
 
     #[test]
@@ -306,7 +365,7 @@ mod test {
             Node::new_child_value(9),
             Node::new_node(Node::new_child_value(8), Node::new_child_value(7))
         );
-        assert_eq!(expected, parse_str_to_tree("[9,[8,7]]", 0).0);
+        assert_eq!(expected, parse("[9,[8,7]]"));
     }
 
     #[test]
@@ -315,7 +374,7 @@ mod test {
             Node::new_node(Node::new_child_value(1), Node::new_child_value(9)),
             Node::new_node(Node::new_child_value(8), Node::new_child_value(5))
         );
-        assert_eq!(expected, parse_str_to_tree("[[1,9],[8,5]]", 0).0);
+        assert_eq!(expected, parse("[[1,9],[8,5]]"));
     }
 
     #[test]
@@ -333,7 +392,7 @@ mod test {
             ),
             Node::new_child_value(9)
         );
-        assert_eq!(expected, parse_str_to_tree("[[[[1,2],[3,4]],[[5,6],[7,8]]],9]", 0).0);
+        assert_eq!(expected, parse("[[[[1,2],[3,4]],[[5,6],[7,8]]],9]"));
     }
 
     #[test]
@@ -357,7 +416,7 @@ mod test {
                 Node::new_child_value(3)
             )
         );
-        assert_eq!(expected, parse_str_to_tree("[[[9,[3,8]],[[0,9],6]],[[[3,7],[4,9]],3]]", 0).0);
+        assert_eq!(expected, parse("[[[9,[3,8]],[[0,9],6]],[[[3,7],[4,9]],3]]"));
     }
 
     #[test]
@@ -384,238 +443,236 @@ mod test {
                 )
             )
         );
-        assert_eq!(expected, parse_str_to_tree("[[[[1,3],[5,3]],[[1,3],[8,7]]],[[[4,9],[6,9]],[[8,2],[7,3]]]]", 0).0);
+        assert_eq!(expected, parse("[[[[1,3],[5,3]],[[1,3],[8,7]]],[[[4,9],[6,9]],[[8,2],[7,3]]]]"));
     }
 
     #[test]
     fn test_explosion_left_most_explosion() {
-        let pre_explosion = parse_str_to_tree("[[[[[9,8],1],2],3],4]", 0).0;
-        let post_explosion = parse_str_to_tree("[[[[0,9],2],3],4]", 0).0;
-        let action = process(pre_explosion).0;
+        let pre_explosion = parse("[[[[[9,8],1],2],3],4]");
+        let post_explosion = parse("[[[[0,9],2],3],4]");
+        let action = process(pre_explosion, &Rules::default()).0;
         assert_eq!(post_explosion, action);
     }
 
     #[test]
     fn test_explosion_right_most_explosion() {
-        let pre_explosion = parse_str_to_tree("[7,[6,[5,[4,[3,2]]]]]", 0).0;
-        let post_explosion = parse_str_to_tree("[7,[6,[5,[7,0]]]]", 0).0;
-        let action = process(pre_explosion).0;
+        let pre_explosion = parse("[7,[6,[5,[4,[3,2]]]]]");
+        let post_explosion = parse("[7,[6,[5,[7,0]]]]");
+        let action = process(pre_explosion, &Rules::default()).0;
         assert_eq!(post_explosion, action);
     }
 
     #[test]
     fn test_explosion_somewhere_in_the_middle() {
-        let pre_explosion = parse_str_to_tree("[[6,[5,[4,[3,2]]]],1]", 0).0;
-        let post_explosion = parse_str_to_tree("[[6,[5,[7,0]]],3]", 0).0;
-        let action = process(pre_explosion).0;
+        let pre_explosion = parse("[[6,[5,[4,[3,2]]]],1]");
+        let post_explosion = parse("[[6,[5,[7,0]]],3]");
+        let action = process(pre_explosion, &Rules::default()).0;
         assert_eq!(post_explosion, action);
     }
 
     #[test]
     fn test_explosion_should_only_affect_left_not_right() {
-        let pre_explosion = parse_str_to_tree("[[3,[2,[1,[7,3]]]],[6,[5,[4,[3,2]]]]]", 0).0;
-        let post_explosion = parse_str_to_tree("[[3,[2,[8,0]]],[9,[5,[4,[3,2]]]]]", 0).0;
-        let action = process(pre_explosion).0;
+        let pre_explosion = parse("[[3,[2,[1,[7,3]]]],[6,[5,[4,[3,2]]]]]");
+        let post_explosion = parse("[[3,[2,[8,0]]],[9,[5,[4,[3,2]]]]]");
+        let action = process(pre_explosion, &Rules::default()).0;
         assert_eq!(post_explosion, action);
     }
 
     #[test]
     fn test_explosion_random_example() {
-        let pre_explosion = parse_str_to_tree("[[3,[2,[8,0]]],[9,[5,[4,[3,2]]]]]", 0).0;
-        let post_explosion = parse_str_to_tree("[[3,[2,[8,0]]],[9,[5,[7,0]]]]", 0).0;
-        let action = process(pre_explosion).0;
+        let pre_explosion = parse("[[3,[2,[8,0]]],[9,[5,[4,[3,2]]]]]");
+        let post_explosion = parse("[[3,[2,[8,0]]],[9,[5,[7,0]]]]");
+        let action = process(pre_explosion, &Rules::default()).0;
         assert_eq!(post_explosion, action);
     }
 
     #[test]
     fn test_star_2() {
-        let left = parse_str_to_tree("[[2,[[7,7],7]],[[5,8],[[9,3],[0,2]]]]", 0).0;
-        let right = parse_str_to_tree("[[[0,[5,8]],[[1,7],[9,6]]],[[4,[1,2]],[[1,4],2]]]", 0).0;
-        let sum = add_and_reduce(left, right);
-        assert_eq!(3993, calc_magnitude(sum));
+        let left = parse("[[2,[[7,7],7]],[[5,8],[[9,3],[0,2]]]]");
+        let right = parse("[[[0,[5,8]],[[1,7],[9,6]]],[[4,[1,2]],[[1,4],2]]]");
+        let sum = add_and_reduce(left, right, &Rules::default());
+        assert_eq!(3993, calc_magnitude(&sum, &Rules::default()));
     }
 
     #[test]
     fn test_split_simple() {
-        let pre_split = parse_str_to_tree("[10,1]", 0).0;
-        let post_split = parse_str_to_tree("[[5,5],1]", 0).0;
-        let action = process(pre_split).0;
+        let pre_split = parse("[10,1]");
+        let post_split = parse("[[5,5],1]");
+        let action = process(pre_split, &Rules::default()).0;
         assert_eq!(post_split, action);
     }
 
     #[test]
     fn test_split_one_at_a_time() {
-        let pre_split = parse_str_to_tree("[10,10]", 0).0;
+        let pre_split = parse("[10,10]");
 
-        let first_split = parse_str_to_tree("[[5,5],10]", 0).0;
-        let action = process(pre_split).0;
+        let first_split = parse("[[5,5],10]");
+        let action = process(pre_split, &Rules::default()).0;
         assert_eq!(first_split, action);
 
-        let second_split = parse_str_to_tree("[[5,5],[5,5]]", 0).0;
-        let action = process(action).0;
+        let second_split = parse("[[5,5],[5,5]]");
+        let action = process(action, &Rules::default()).0;
         assert_eq!(second_split, action);
     }
 
     #[test]
     fn test_sequence() {
-        let pre_loop = parse_str_to_tree("[[[[[4,3],4],4],[7,[[8,4],9]]],[1,1]]", 0).0;
+        let pre_loop = parse("[[[[[4,3],4],4],[7,[[8,4],9]]],[1,1]]");
 
-        let step_1 = parse_str_to_tree("[[[[0,7],4],[7,[[8,4],9]]],[1,1]]", 0).0;
-        let mut action = process(pre_loop).0;
+        let step_1 = parse("[[[[0,7],4],[7,[[8,4],9]]],[1,1]]");
+        let mut action = process(pre_loop, &Rules::default()).0;
         assert_eq!(step_1, action);
 
-        let step_2 = parse_str_to_tree("[[[[0,7],4],[15,[0,13]]],[1,1]]", 0).0;
-        action = process(action).0;
+        let step_2 = parse("[[[[0,7],4],[15,[0,13]]],[1,1]]");
+        action = process(action, &Rules::default()).0;
         assert_eq!(step_2, action);
 
-        let step_3 = parse_str_to_tree("[[[[0,7],4],[[7,8],[0,13]]],[1,1]]", 0).0;
-        action = process(action).0;
+        let step_3 = parse("[[[[0,7],4],[[7,8],[0,13]]],[1,1]]");
+        action = process(action, &Rules::default()).0;
         assert_eq!(step_3, action);
 
-        let step_4 = parse_str_to_tree("[[[[0,7],4],[[7,8],[0,[6,7]]]],[1,1]]", 0).0;
-        action = process(action).0;
+        let step_4 = parse("[[[[0,7],4],[[7,8],[0,[6,7]]]],[1,1]]");
+        action = process(action, &Rules::default()).0;
         assert_eq!(step_4, action);
 
-        let step_5 = parse_str_to_tree("[[[[0,7],4],[[7,8],[6,0]]],[8,1]]", 0).0;
-        action = process(action).0;
+        let step_5 = parse("[[[[0,7],4],[[7,8],[6,0]]],[8,1]]");
+        action = process(action, &Rules::default()).0;
         assert_eq!(step_5, action);
     }
 
     #[test]
     fn test_magnitude_example_1() {
-        let tree = parse_str_to_tree("[[1,2],[[3,4],5]]", 0).0;
-        assert_eq!(143, calc_magnitude(tree));
+        let tree = parse("[[1,2],[[3,4],5]]");
+        assert_eq!(143, calc_magnitude(&tree, &Rules::default()));
     }
 
     #[test]
     fn test_magnitude_example_2() {
-        let tree = parse_str_to_tree("[[[[0,7],4],[[7,8],[6,0]]],[8,1]]", 0).0;
-        assert_eq!(1384, calc_magnitude(tree));
+        let tree = parse("[[[[0,7],4],[[7,8],[6,0]]],[8,1]]");
+        assert_eq!(1384, calc_magnitude(&tree, &Rules::default()));
     }
 
     #[test]
     fn test_magnitude_example_3() {
-        let tree = parse_str_to_tree("[[[[1,1],[2,2]],[3,3]],[4,4]]", 0).0;
-        assert_eq!(445, calc_magnitude(tree));
+        let tree = parse("[[[[1,1],[2,2]],[3,3]],[4,4]]");
+        assert_eq!(445, calc_magnitude(&tree, &Rules::default()));
     }
 
     #[test]
     fn test_magnitude_example_4() {
-        let tree = parse_str_to_tree("[[[[3,0],[5,3]],[4,4]],[5,5]]", 0).0;
-        assert_eq!(791, calc_magnitude(tree));
+        let tree = parse("[[[[3,0],[5,3]],[4,4]],[5,5]]");
+        assert_eq!(791, calc_magnitude(&tree, &Rules::default()));
     }
 
     #[test]
     fn test_magnitude_example_5() {
-        let tree = parse_str_to_tree("[[[[5,0],[7,4]],[5,5]],[6,6]]", 0).0;
-        assert_eq!(1137, calc_magnitude(tree));
+        let tree = parse("[[[[5,0],[7,4]],[5,5]],[6,6]]");
+        assert_eq!(1137, calc_magnitude(&tree, &Rules::default()));
     }
 
     #[test]
     fn test_magnitude_example_6() {
-        let tree = parse_str_to_tree("[[[[8,7],[7,7]],[[8,6],[7,7]]],[[[0,7],[6,6]],[8,7]]]", 0).0;
-        assert_eq!(3488, calc_magnitude(tree));
+        let tree = parse("[[[[8,7],[7,7]],[[8,6],[7,7]]],[[[0,7],[6,6]],[8,7]]]");
+        assert_eq!(3488, calc_magnitude(&tree, &Rules::default()));
     }
 
     #[test]
     fn test_final_sum_1() {
-        let left = parse_str_to_tree("[[[0,[4,5]],[0,0]],[[[4,5],[2,6]],[9,5]]]", 0).0;
-        let right = parse_str_to_tree("[7,[[[3,7],[4,3]],[[6,3],[8,8]]]]", 0).0;
-
-        let sum = parse_str_to_tree("[[[[4,0],[5,4]],[[7,7],[6,0]]],[[8,[7,7]],[[7,9],[5,0]]]]", 0).0;
+        let left = "[[[0,[4,5]],[0,0]],[[[4,5],[2,6]],[9,5]]]".parse::<SnailfishNumber>().unwrap();
+        let right = "[7,[[[3,7],[4,3]],[[6,3],[8,8]]]]".parse::<SnailfishNumber>().unwrap();
 
-        assert_eq!(sum, add_and_reduce(left, right));
+        assert_eq!("[[[[4,0],[5,4]],[[7,7],[6,0]]],[[8,[7,7]],[[7,9],[5,0]]]]", (left + right).to_string());
     }
 
     #[test]
     fn test_final_sum_2() {
-        let left = parse_str_to_tree("[[[[4,0],[5,4]],[[7,7],[6,0]]],[[8,[7,7]],[[7,9],[5,0]]]]", 0).0;
-        let right = parse_str_to_tree("[[2,[[0,8],[3,4]]],[[[6,7],1],[7,[1,6]]]]", 0).0;
+        let left = "[[[[4,0],[5,4]],[[7,7],[6,0]]],[[8,[7,7]],[[7,9],[5,0]]]]".parse::<SnailfishNumber>().unwrap();
+        let right = "[[2,[[0,8],[3,4]]],[[[6,7],1],[7,[1,6]]]]".parse::<SnailfishNumber>().unwrap();
 
-        let sum = parse_str_to_tree("[[[[6,7],[6,7]],[[7,7],[0,7]]],[[[8,7],[7,7]],[[8,8],[8,0]]]]", 0).0;
-
-        assert_eq!(sum, add_and_reduce(left, right));
+        assert_eq!("[[[[6,7],[6,7]],[[7,7],[0,7]]],[[[8,7],[7,7]],[[8,8],[8,0]]]]", (left + right).to_string());
     }
 
     #[test]
     fn test_final_sum_3() {
-        let left = parse_str_to_tree("[[[[6,7],[6,7]],[[7,7],[0,7]]],[[[8,7],[7,7]],[[8,8],[8,0]]]]", 0).0;
-        let right = parse_str_to_tree("[[[[2,4],7],[6,[0,5]]],[[[6,8],[2,8]],[[2,1],[4,5]]]]", 0).0;
-
-        let sum = parse_str_to_tree("[[[[7,0],[7,7]],[[7,7],[7,8]]],[[[7,7],[8,8]],[[7,7],[8,7]]]]", 0).0;
+        let left = "[[[[6,7],[6,7]],[[7,7],[0,7]]],[[[8,7],[7,7]],[[8,8],[8,0]]]]".parse::<SnailfishNumber>().unwrap();
+        let right = "[[[[2,4],7],[6,[0,5]]],[[[6,8],[2,8]],[[2,1],[4,5]]]]".parse::<SnailfishNumber>().unwrap();
 
-        assert_eq!(sum, add_and_reduce(left, right));
+        assert_eq!("[[[[7,0],[7,7]],[[7,7],[7,8]]],[[[7,7],[8,8]],[[7,7],[8,7]]]]", (left + right).to_string());
     }
 
     #[test]
     fn test_final_sum_4() {
-        let left = parse_str_to_tree("[[[[7,0],[7,7]],[[7,7],[7,8]]],[[[7,7],[8,8]],[[7,7],[8,7]]]]", 0).0;
-        let right = parse_str_to_tree("[7,[5,[[3,8],[1,4]]]]", 0).0;
-
-        let sum = parse_str_to_tree("[[[[7,7],[7,8]],[[9,5],[8,7]]],[[[6,8],[0,8]],[[9,9],[9,0]]]]", 0).0;
+        let left = "[[[[7,0],[7,7]],[[7,7],[7,8]]],[[[7,7],[8,8]],[[7,7],[8,7]]]]".parse::<SnailfishNumber>().unwrap();
+        let right = "[7,[5,[[3,8],[1,4]]]]".parse::<SnailfishNumber>().unwrap();
 
-        assert_eq!(sum, add_and_reduce(left, right));
+        assert_eq!("[[[[7,7],[7,8]],[[9,5],[8,7]]],[[[6,8],[0,8]],[[9,9],[9,0]]]]", (left + right).to_string());
     }
 
     #[test]
     fn test_final_sum_5() {
-        let left = parse_str_to_tree("[[[[7,7],[7,8]],[[9,5],[8,7]]],[[[6,8],[0,8]],[[9,9],[9,0]]]]", 0).0;
-        let right = parse_str_to_tree("[[2,[2,2]],[8,[8,1]]]", 0).0;
+        let left = "[[[[7,7],[7,8]],[[9,5],[8,7]]],[[[6,8],[0,8]],[[9,9],[9,0]]]]".parse::<SnailfishNumber>().unwrap();
+        let right = "[[2,[2,2]],[8,[8,1]]]".parse::<SnailfishNumber>().unwrap();
 
-        let sum = parse_str_to_tree("[[[[6,6],[6,6]],[[6,0],[6,7]]],[[[7,7],[8,9]],[8,[8,1]]]]", 0).0;
-
-        assert_eq!(sum, add_and_reduce(left, right));
+        assert_eq!("[[[[6,6],[6,6]],[[6,0],[6,7]]],[[[7,7],[8,9]],[8,[8,1]]]]", (left + right).to_string());
     }
 
     #[test]
     fn test_final_sum_6() {
-        let left = parse_str_to_tree("[[[[6,6],[6,6]],[[6,0],[6,7]]],[[[7,7],[8,9]],[8,[8,1]]]]", 0).0;
-        let right = parse_str_to_tree("[2,9]", 0).0;
-
-        let sum = parse_str_to_tree("[[[[6,6],[7,7]],[[0,7],[7,7]]],[[[5,5],[5,6]],9]]", 0).0;
+        let left = "[[[[6,6],[6,6]],[[6,0],[6,7]]],[[[7,7],[8,9]],[8,[8,1]]]]".parse::<SnailfishNumber>().unwrap();
+        let right = "[2,9]".parse::<SnailfishNumber>().unwrap();
 
-        assert_eq!(sum, add_and_reduce(left, right));
+        assert_eq!("[[[[6,6],[7,7]],[[0,7],[7,7]]],[[[5,5],[5,6]],9]]", (left + right).to_string());
     }
 
     #[test]
     fn test_final_sum_7() {
-        let left = parse_str_to_tree("[[[[6,6],[7,7]],[[0,7],[7,7]]],[[[5,5],[5,6]],9]]", 0).0;
-        let right = parse_str_to_tree("[1,[[[9,3],9],[[9,0],[0,7]]]]", 0).0;
+        let left = "[[[[6,6],[7,7]],[[0,7],[7,7]]],[[[5,5],[5,6]],9]]".parse::<SnailfishNumber>().unwrap();
+        let right = "[1,[[[9,3],9],[[9,0],[0,7]]]]".parse::<SnailfishNumber>().unwrap();
 
-        let sum = parse_str_to_tree("[[[[7,8],[6,7]],[[6,8],[0,8]]],[[[7,7],[5,0]],[[5,5],[5,6]]]]", 0).0;
-
-        assert_eq!(sum, add_and_reduce(left, right));
+        assert_eq!("[[[[7,8],[6,7]],[[6,8],[0,8]]],[[[7,7],[5,0]],[[5,5],[5,6]]]]", (left + right).to_string());
     }
 
     #[test]
     fn test_final_sum_8() {
-        let left = parse_str_to_tree("[[[[7,8],[6,7]],[[6,8],[0,8]]],[[[7,7],[5,0]],[[5,5],[5,6]]]]", 0).0;
-        let right = parse_str_to_tree("[[[5,[7,4]],7],1]", 0).0;
-
-        let sum = parse_str_to_tree("[[[[7,7],[7,7]],[[8,7],[8,7]]],[[[7,0],[7,7]],9]]", 0).0;
+        let left = "[[[[7,8],[6,7]],[[6,8],[0,8]]],[[[7,7],[5,0]],[[5,5],[5,6]]]]".parse::<SnailfishNumber>().unwrap();
+        let right = "[[[5,[7,4]],7],1]".parse::<SnailfishNumber>().unwrap();
 
-        assert_eq!(sum, add_and_reduce(left, right));
+        assert_eq!("[[[[7,7],[7,7]],[[8,7],[8,7]]],[[[7,0],[7,7]],9]]", (left + right).to_string());
     }
 
     #[test]
     fn test_final_sum_9() {
-        let left = parse_str_to_tree("[[[[7,7],[7,7]],[[8,7],[8,7]]],[[[7,0],[7,7]],9]]", 0).0;
-        let right = parse_str_to_tree("[[[[4,2],2],6],[8,7]]", 0).0;
-
-        let sum = parse_str_to_tree("[[[[8,7],[7,7]],[[8,6],[7,7]]],[[[0,7],[6,6]],[8,7]]]", 0).0;
+        let left = "[[[[7,7],[7,7]],[[8,7],[8,7]]],[[[7,0],[7,7]],9]]".parse::<SnailfishNumber>().unwrap();
+        let right = "[[[[4,2],2],6],[8,7]]".parse::<SnailfishNumber>().unwrap();
 
-        assert_eq!(sum, add_and_reduce(left, right));
+        assert_eq!("[[[[8,7],[7,7]],[[8,6],[7,7]]],[[[0,7],[6,6]],[8,7]]]", (left + right).to_string());
     }
 
     #[test]
     fn test_calculate_one() -> Result<(), Box<dyn Error>> {
-        assert_eq!(4140, run_part_one("./inputs/day_18_test.in", Box::new(Day18))?);
+        assert_eq!(4140, run_part_one("./inputs/day_18_test.in", Box::new(Day18(Rules::default())))?);
         Ok(())
     }
 
     #[test]
     fn test_calculate_two() -> Result<(), Box<dyn Error>> {
-        assert_eq!(3993, run_part_two("./inputs/day_18_test.in", Box::new(Day18))?);
+        assert_eq!(3993, run_part_two("./inputs/day_18_test.in", Box::new(Day18(Rules::default())))?);
         Ok(())
     }
+
+    #[test]
+    fn test_largest_pair() -> Result<(), Box<dyn Error>> {
+        let input = Day18(Rules::default()).parse(read_file("./inputs/day_18_test.in")?)?;
+        for threads in [1, 2, 3, 16] {
+            assert_eq!(Some((3993, (8, 0))), largest_pair(&input, threads, &Rules::default()));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_largest_pair_ties_go_to_the_earliest_pair() {
+        let input = vec!["[1,1]".parse().unwrap(), "[1,1]".parse().unwrap(), "[1,1]".parse().unwrap()];
+        assert_eq!(Some((25, (0, 1))), largest_pair(&input, 3, &Rules::default()));
+        assert_eq!(None, largest_pair(&input[..1], 3, &Rules::default()));
+    }
 }
\ No newline at end of file
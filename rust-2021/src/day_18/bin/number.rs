@@ -0,0 +1,204 @@
+use std::error::Error;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::iter::Sum;
+use std::ops::Add;
+use std::str::FromStr;
+use crate::{ChildNode, Node, NodeKind, add_and_reduce, calc_magnitude, reduce};
+use crate::rules::Rules;
+
+/// A snailfish number: always a pair at the top.
+#[derive(Debug, PartialEq, Clone)]
+pub struct SnailfishNumber(pub ChildNode);
+
+impl SnailfishNumber {
+    /// Parses and then reduces, as inputs that are reduced under one set of
+    /// rules needn't be under another.
+    pub fn parse_with(s: &str, rules: &Rules) -> Result<Self, ParseSnailfishError> {
+        let number = s.parse::<SnailfishNumber>()?;
+        Ok(SnailfishNumber(reduce(number.0, rules)))
+    }
+
+    pub fn add_with(self, rhs: SnailfishNumber, rules: &Rules) -> SnailfishNumber {
+        SnailfishNumber(add_and_reduce(self.0, rhs.0, rules))
+    }
+
+    #[cfg(test)]
+    pub fn magnitude(&self) -> i64 {
+        self.magnitude_with(&Rules::default())
+    }
+
+    pub fn magnitude_with(&self, rules: &Rules) -> i64 {
+        calc_magnitude(&self.0, rules)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ParseSnailfishError {
+    /// `found` is `None` when the input ended early.
+    Unexpected { column: usize, expected: &'static str, found: Option<char> },
+    NumberTooLarge { column: usize }
+}
+
+impl Display for ParseSnailfishError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseSnailfishError::Unexpected { column, expected, found: Some(c) } =>
+                write!(f, "column {}: expected {} but found {:?}", column, expected, c),
+            ParseSnailfishError::Unexpected { column, expected, found: None } =>
+                write!(f, "column {}: expected {} but the input ended", column, expected),
+            ParseSnailfishError::NumberTooLarge { column } =>
+                write!(f, "column {}: number is too large", column)
+        }
+    }
+}
+
+impl Error for ParseSnailfishError {}
+
+fn expect(s: &[u8], pos: usize, c: u8, expected: &'static str) -> Result<usize, ParseSnailfishError> {
+    match s.get(pos) {
+        Some(found) if *found == c => Ok(pos + 1),
+        found => Err(ParseSnailfishError::Unexpected { column: pos + 1, expected, found: found.map(|c| *c as char) })
+    }
+}
+
+fn parse_node(s: &[u8], pos: usize) -> Result<(ChildNode, usize), ParseSnailfishError> {
+    match s.get(pos) {
+        Some(b'[') => {
+            let (left, pos) = parse_node(s, pos + 1)?;
+            let pos = expect(s, pos, b',', "','")?;
+            let (right, pos) = parse_node(s, pos)?;
+            let pos = expect(s, pos, b']', "']'")?;
+            Ok((Node::new_node(left, right), pos))
+        },
+        Some(c) if c.is_ascii_digit() => {
+            let end = s[pos..].iter()
+                .position(|c| !c.is_ascii_digit())
+                .map_or(s.len(), |n| pos + n);
+            let value = s[pos..end].iter()
+                .try_fold(0i64, |n, d| n.checked_mul(10)?.checked_add((d - b'0') as i64))
+                .ok_or(ParseSnailfishError::NumberTooLarge { column: pos + 1 })?;
+            Ok((Node::new_child_value(value), end))
+        },
+        found => Err(ParseSnailfishError::Unexpected {
+            column: pos + 1, expected: "'[' or a digit", found: found.map(|c| *c as char)
+        })
+    }
+}
+
+impl FromStr for SnailfishNumber {
+    type Err = ParseSnailfishError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.as_bytes();
+        expect(s, 0, b'[', "'['")?;
+
+        let (node, pos) = parse_node(s, 0)?;
+        if let Some(c) = s.get(pos) {
+            return Err(ParseSnailfishError::Unexpected { column: pos + 1, expected: "the end", found: Some(*c as char) });
+        }
+
+        Ok(SnailfishNumber(node))
+    }
+}
+
+fn write_node(f: &mut Formatter<'_>, node: &ChildNode) -> fmt::Result {
+    match node {
+        None => Ok(()),
+        Some(n) => match n.kind {
+            NodeKind::Value(i) => write!(f, "{}", i),
+            NodeKind::Branch => {
+                write!(f, "[")?;
+                write_node(f, &n.left)?;
+                write!(f, ",")?;
+                write_node(f, &n.right)?;
+                write!(f, "]")
+            }
+        }
+    }
+}
+
+impl Display for SnailfishNumber {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write_node(f, &self.0)
+    }
+}
+
+impl Add for SnailfishNumber {
+    type Output = SnailfishNumber;
+
+    /// Adds and fully reduces under the default rules.
+    fn add(self, rhs: SnailfishNumber) -> SnailfishNumber {
+        self.add_with(rhs, &Rules::default())
+    }
+}
+
+impl Sum for SnailfishNumber {
+    /// Adds left to right. Panics on an empty iterator, as there's no zero
+    /// snailfish number to start from.
+    fn sum<I: Iterator<Item = SnailfishNumber>>(iter: I) -> Self {
+        iter.reduce(|sum, n| sum + n).expect("can't sum zero snailfish numbers")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn parse(s: &str) -> SnailfishNumber {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        for s in ["[1,2]", "[[1,2],3]", "[[[[1,3],[5,3]],[[1,3],[8,7]]],[[[4,9],[6,9]],[[8,2],[7,3]]]]", "[10,[123,0]]"] {
+            assert_eq!(s, parse(s).to_string());
+        }
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = |s: &str| s.parse::<SnailfishNumber>().unwrap_err();
+        assert_eq!(ParseSnailfishError::Unexpected { column: 1, expected: "'['", found: Some('1') }, error("1"));
+        assert_eq!(ParseSnailfishError::Unexpected { column: 1, expected: "'['", found: None }, error(""));
+        assert_eq!(ParseSnailfishError::Unexpected { column: 5, expected: "']'", found: None }, error("[1,2"));
+        assert_eq!(ParseSnailfishError::Unexpected { column: 3, expected: "','", found: Some(']') }, error("[1]"));
+        assert_eq!(ParseSnailfishError::Unexpected { column: 4, expected: "'[' or a digit", found: Some('x') }, error("[1,x]"));
+        assert_eq!(ParseSnailfishError::Unexpected { column: 6, expected: "the end", found: Some(']') }, error("[1,2]]"));
+        assert_eq!(ParseSnailfishError::NumberTooLarge { column: 2 }, error("[99999999999999999999,1]"));
+        assert_eq!("column 3: expected ',' but found ']'", error("[1]").to_string());
+        assert_eq!("column 5: expected ']' but the input ended", error("[1,2").to_string());
+    }
+
+    #[test]
+    fn test_add() {
+        let sum = parse("[[[[4,3],4],4],[7,[[8,4],9]]]") + parse("[1,1]");
+        assert_eq!("[[[[0,7],4],[[7,8],[6,0]]],[8,1]]", sum.to_string());
+    }
+
+    #[test]
+    fn test_sum() {
+        let sum = ["[1,1]", "[2,2]", "[3,3]", "[4,4]", "[5,5]", "[6,6]"].iter()
+            .map(|s| parse(s))
+            .sum::<SnailfishNumber>();
+        assert_eq!("[[[[5,0],[7,4]],[5,5]],[6,6]]", sum.to_string());
+        assert_eq!(1137, sum.magnitude());
+    }
+
+    #[test]
+    fn test_sum_larger_example() {
+        let sum = [
+            "[[[0,[4,5]],[0,0]],[[[4,5],[2,6]],[9,5]]]",
+            "[7,[[[3,7],[4,3]],[[6,3],[8,8]]]]",
+            "[[2,[[0,8],[3,4]]],[[[6,7],1],[7,[1,6]]]]",
+            "[[[[2,4],7],[6,[0,5]]],[[[6,8],[2,8]],[[2,1],[4,5]]]]",
+            "[7,[5,[[3,8],[1,4]]]]",
+            "[[2,[2,2]],[8,[8,1]]]",
+            "[2,9]",
+            "[1,[[[9,3],9],[[9,0],[0,7]]]]",
+            "[[[5,[7,4]],7],1]",
+            "[[[[4,2],2],6],[8,7]]"
+        ].iter().map(|s| parse(s)).sum::<SnailfishNumber>();
+        assert_eq!("[[[[8,7],[7,7]],[[8,6],[7,7]]],[[[0,7],[6,6]],[8,7]]]", sum.to_string());
+    }
+}
@@ -0,0 +1,110 @@
+/// The constants behind reduction and magnitude. The default is the puzzle's:
+/// pairs nested inside four pairs explode, numbers above 9 split, and a pair's
+/// magnitude weighs its left by 3 and its right by 2.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Rules {
+    explode_depth: usize,
+    split_above: i64,
+    left_weight: i64,
+    right_weight: i64
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Rules { explode_depth: 4, split_above: 9, left_weight: 3, right_weight: 2 }
+    }
+}
+
+impl Rules {
+    /// Fails on rules that break reduction: an explode depth of 0 would
+    /// explode the outermost pair itself, and splitting anything below 2
+    /// would split 1 into 0 and 1 forever.
+    pub fn new(explode_depth: usize, split_above: i64, left_weight: i64, right_weight: i64) -> Result<Self, String> {
+        if explode_depth == 0 {
+            return Err("the explode depth must be at least 1".to_string());
+        }
+        if split_above < 1 {
+            return Err(format!("numbers above {} can't all be split", split_above));
+        }
+
+        Ok(Rules { explode_depth, split_above, left_weight, right_weight })
+    }
+
+    /// A pair of regular numbers explodes once it's nested inside at least
+    /// this many pairs.
+    pub fn explode_depth(&self) -> usize {
+        self.explode_depth
+    }
+
+    /// Regular numbers greater than this split.
+    pub fn split_above(&self) -> i64 {
+        self.split_above
+    }
+
+    pub fn left_weight(&self) -> i64 {
+        self.left_weight
+    }
+
+    pub fn right_weight(&self) -> i64 {
+        self.right_weight
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::number::SnailfishNumber;
+    use super::*;
+
+    fn add(left: &str, right: &str, rules: &Rules) -> String {
+        let left = left.parse::<SnailfishNumber>().unwrap();
+        left.add_with(right.parse().unwrap(), rules).to_string()
+    }
+
+    #[test]
+    fn test_shallow_explode_depth() {
+        // [[[1,2],3],[4,5]]: [1,2] goes, pushing 2 into the 3; then [0,5] goes,
+        // pushing 5 into the 4; then [9,5] goes, pushing 9 into the 0.
+        let rules = Rules::new(1, 9, 3, 2).unwrap();
+        assert_eq!("[9,0]", add("[[1,2],3]", "[4,5]", &rules));
+    }
+
+    #[test]
+    fn test_deeper_explode_depth() {
+        assert_eq!("[[[[0,5],4],5],[6,7]]", add("[[[[1,2],3],4],5]", "[6,7]", &Rules::default()));
+
+        let rules = Rules::new(5, 9, 3, 2).unwrap();
+        assert_eq!("[[[[[1,2],3],4],5],[6,7]]", add("[[[[1,2],3],4],5]", "[6,7]", &rules));
+    }
+
+    #[test]
+    fn test_split_threshold() {
+        assert_eq!("[[[7,8],1],[1,1]]", add("[15,1]", "[1,1]", &Rules::default()));
+
+        let rules = Rules::new(4, 15, 3, 2).unwrap();
+        assert_eq!("[[15,1],[1,1]]", add("[15,1]", "[1,1]", &rules));
+        assert_eq!("[[[8,8],1],[1,1]]", add("[16,1]", "[1,1]", &rules));
+    }
+
+    #[test]
+    fn test_magnitude_weights() {
+        let number = "[[1,2],[[3,4],5]]".parse::<SnailfishNumber>().unwrap();
+        assert_eq!(143, number.magnitude());
+
+        let rules = Rules::new(4, 9, 1, 1).unwrap();
+        assert_eq!(15, number.magnitude_with(&rules));
+    }
+
+    #[test]
+    fn test_parse_reduces_under_the_rules() {
+        let rules = Rules::new(4, 4, 3, 2).unwrap();
+        assert_eq!("[[2,3],4]", SnailfishNumber::parse_with("[5,4]", &rules).unwrap().to_string());
+        assert_eq!("[5,4]", SnailfishNumber::parse_with("[5,4]", &Rules::default()).unwrap().to_string());
+    }
+
+    #[test]
+    fn test_degenerate_rules() {
+        assert_eq!(Ok(Rules::default()), Rules::new(4, 9, 3, 2));
+        assert_eq!(Err("the explode depth must be at least 1".to_string()), Rules::new(0, 9, 3, 2));
+        assert_eq!(Err("numbers above 0 can't all be split".to_string()), Rules::new(4, 0, 3, 2));
+    }
+}
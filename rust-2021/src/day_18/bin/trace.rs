@@ -0,0 +1,138 @@
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use crate::{add, process};
+use crate::number::SnailfishNumber;
+use crate::rules::Rules;
+
+/// Which half of a pair to step into on the way down from the top.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Side {
+    Left,
+    Right
+}
+
+/// One reduction step. `path` leads from the top to the pair that exploded
+/// or the number that split.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Action {
+    /// `left` and `right` are the values pushed out to the neighbours.
+    Explode { path: Vec<Side>, left: i64, right: i64 },
+    Split { path: Vec<Side>, value: i64 }
+}
+
+fn write_path(f: &mut Formatter<'_>, path: &[Side]) -> fmt::Result {
+    if path.is_empty() {
+        return write!(f, "the top");
+    }
+    for side in path {
+        write!(f, "{}", if *side == Side::Left { 'L' } else { 'R' })?;
+    }
+    Ok(())
+}
+
+impl Display for Action {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Action::Explode { path, left, right } => {
+                write!(f, "explode [{},{}] at ", left, right)?;
+                write_path(f, path)
+            },
+            Action::Split { path, value } => {
+                write!(f, "split {} at ", value)?;
+                write_path(f, path)
+            }
+        }
+    }
+}
+
+/// How an addition got to its result: the unreduced sum, then each action
+/// with the number it left behind.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Trace {
+    pub start: SnailfishNumber,
+    pub steps: Vec<(Action, SnailfishNumber)>
+}
+
+/// Prints the same lines as the puzzle's worked example.
+impl Display for Trace {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{:16}{}", "after addition:", self.start)?;
+        for (action, number) in &self.steps {
+            let label = match action {
+                Action::Explode { .. } => "after explode:",
+                Action::Split { .. } => "after split:"
+            };
+            write!(f, "\n{:16}{}", label, number)?;
+        }
+        Ok(())
+    }
+}
+
+impl SnailfishNumber {
+    /// Like `add_with`, but also returns every step of the reduction.
+    pub fn add_traced(self, rhs: SnailfishNumber, rules: &Rules) -> (SnailfishNumber, Trace) {
+        let mut node = add(self.0, rhs.0);
+        let start = SnailfishNumber(node.clone());
+        let mut steps = vec![];
+
+        loop {
+            let (next, action) = process(node, rules);
+            node = next;
+            match action {
+                Some(action) => steps.push((action, SnailfishNumber(node.clone()))),
+                None => break
+            }
+        }
+
+        (SnailfishNumber(node), Trace { start, steps })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use super::Side::{Left, Right};
+
+    fn parse(s: &str) -> SnailfishNumber {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_trace_matches_example() {
+        let (sum, trace) = parse("[[[[4,3],4],4],[7,[[8,4],9]]]").add_traced(parse("[1,1]"), &Rules::default());
+        let expected = "\
+after addition: [[[[[4,3],4],4],[7,[[8,4],9]]],[1,1]]
+after explode:  [[[[0,7],4],[7,[[8,4],9]]],[1,1]]
+after explode:  [[[[0,7],4],[15,[0,13]]],[1,1]]
+after split:    [[[[0,7],4],[[7,8],[0,13]]],[1,1]]
+after split:    [[[[0,7],4],[[7,8],[0,[6,7]]]],[1,1]]
+after explode:  [[[[0,7],4],[[7,8],[6,0]]],[8,1]]";
+
+        assert_eq!(expected, trace.to_string());
+        assert_eq!(parse("[[[[4,3],4],4],[7,[[8,4],9]]]") + parse("[1,1]"), sum);
+    }
+
+    #[test]
+    fn test_trace_actions() {
+        let (_, trace) = parse("[[[[4,3],4],4],[7,[[8,4],9]]]").add_traced(parse("[1,1]"), &Rules::default());
+        let actions = trace.steps.into_iter().map(|(action, _)| action).collect::<Vec<_>>();
+
+        assert_eq!(vec![
+            Action::Explode { path: vec![Left, Left, Left, Left], left: 4, right: 3 },
+            Action::Explode { path: vec![Left, Right, Right, Left], left: 8, right: 4 },
+            Action::Split { path: vec![Left, Right, Left], value: 15 },
+            Action::Split { path: vec![Left, Right, Right, Right], value: 13 },
+            Action::Explode { path: vec![Left, Right, Right, Right], left: 6, right: 7 }
+        ], actions);
+        assert_eq!("explode [4,3] at LLLL", actions[0].to_string());
+        assert_eq!("split 15 at LRL", actions[2].to_string());
+    }
+
+    #[test]
+    fn test_trace_without_reduction() {
+        let (sum, trace) = parse("[1,2]").add_traced(parse("[[3,4],5]"), &Rules::default());
+        assert!(trace.steps.is_empty());
+        assert_eq!(trace.start, sum);
+        assert_eq!("after addition: [[1,2],[[3,4],5]]", trace.to_string());
+    }
+}
@@ -0,0 +1,381 @@
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::error::Error;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use crate::{Coord, Scanner};
+
+/// How strict matching two scanners is. The default is the puzzle's exact
+/// data: 12 shared beacons, no stray readings, and a range of 1000.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MatchConfig {
+    /// How many beacons two scanners must agree on to count as overlapping.
+    pub overlap: usize,
+    /// How many readings each scanner of a matched pair may have that the
+    /// other contradicts: a spurious one inside the other's range with
+    /// nothing there, or a beacon the other sees in its range but it missed.
+    pub tolerance: usize,
+    /// How far a scanner sees along each axis.
+    pub range: i32
+}
+
+impl Default for MatchConfig {
+    fn default() -> Self {
+        MatchConfig { overlap: 12, tolerance: 0, range: 1000 }
+    }
+}
+
+/// One of the 24 ways a scanner can be facing. Global axis `k` is the
+/// scanner's axis `axes[k]`, multiplied by `signs[k]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Orientation {
+    axes: [usize; 3],
+    signs: [i32; 3]
+}
+
+impl Orientation {
+    pub const IDENTITY: Orientation = Orientation { axes: [0, 1, 2], signs: [1, 1, 1] };
+
+    /// Every rotation, starting with the identity. Reflections are left out,
+    /// which is what keeping only the even-determinant matrices does.
+    pub fn all() -> Vec<Orientation> {
+        let permutations = [([0, 1, 2], 1), ([1, 2, 0], 1), ([2, 0, 1], 1), ([0, 2, 1], -1), ([1, 0, 2], -1), ([2, 1, 0], -1)];
+
+        let mut orientations = vec![];
+        for (axes, parity) in permutations {
+            for bits in 0..8 {
+                let signs = [0, 1, 2].map(|k| if bits & (1 << k) == 0 { 1 } else { -1 });
+                if parity * signs.iter().product::<i32>() == 1 {
+                    orientations.push(Orientation { axes, signs });
+                }
+            }
+        }
+        orientations
+    }
+
+    pub fn apply(&self, c: Coord) -> Coord {
+        let v = [c.x, c.y, c.z];
+        Coord {
+            x: self.signs[0] * v[self.axes[0]],
+            y: self.signs[1] * v[self.axes[1]],
+            z: self.signs[2] * v[self.axes[2]]
+        }
+    }
+}
+
+/// Shows which of the scanner's axes each global axis comes from, so the
+/// identity is `(+x, +y, +z)`.
+impl Display for Orientation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let axis = |k: usize| format!("{}{}", if self.signs[k] > 0 { '+' } else { '-' }, ['x', 'y', 'z'][self.axes[k]]);
+        write!(f, "({}, {}, {})", axis(0), axis(1), axis(2))
+    }
+}
+
+/// Where a scanner sits and which way it faces, relative to scanner 0.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Placement {
+    pub position: Coord,
+    pub orientation: Orientation
+}
+
+impl Placement {
+    pub fn apply(&self, c: Coord) -> Coord {
+        self.orientation.apply(c) + self.position
+    }
+}
+
+pub struct Alignment {
+    /// Indexed like the scanners that were aligned.
+    pub placements: Vec<Placement>,
+    /// Every distinct beacon relative to scanner 0, sorted.
+    pub beacons: Vec<Coord>,
+    /// The lowest-numbered scanner that reads each beacon, indexed like
+    /// `beacons`.
+    pub seen_by: Vec<usize>
+}
+
+#[derive(Debug, PartialEq)]
+pub enum AlignError {
+    /// These scanners couldn't be matched with any placed scanner.
+    Unaligned(Vec<usize>),
+    /// More than one placement of `scanner` matched `reference`.
+    Ambiguous { scanner: usize, reference: usize, placements: Vec<Placement> }
+}
+
+impl Display for AlignError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            AlignError::Unaligned(scanners) => {
+                let scanners = scanners.iter().map(|i| i.to_string()).collect::<Vec<_>>();
+                write!(f, "scanners {} don't overlap with any placed scanner", scanners.join(", "))
+            },
+            AlignError::Ambiguous { scanner, reference, placements } => {
+                write!(f, "scanner {} matches scanner {} in {} ways:", scanner, reference, placements.len())?;
+                for placement in placements {
+                    write!(f, " at {} facing {};", placement.position, placement.orientation)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Error for AlignError {}
+
+/// The squared distances between every two of a scanner's beacons, sorted.
+/// They don't change with orientation or position, so two scanners sharing
+/// `overlap` beacons share at least that many choose 2 of them.
+fn fingerprint(beacons: &[Coord]) -> Vec<i64> {
+    let mut distances = vec![];
+    for (i, a) in beacons.iter().enumerate() {
+        for b in &beacons[i + 1..] {
+            let d = *a - *b;
+            distances.push([d.x, d.y, d.z].iter().map(|n| (*n as i64).pow(2)).sum());
+        }
+    }
+    distances.sort_unstable();
+    distances
+}
+
+/// How many distances two sorted fingerprints have in common, counting
+/// repeats.
+fn shared(a: &[i64], b: &[i64]) -> usize {
+    let (mut i, mut j, mut count) = (0, 0, 0);
+    while i < a.len() && j < b.len() {
+        if a[i] < b[j] {
+            i += 1;
+        } else if a[i] > b[j] {
+            j += 1;
+        } else {
+            count += 1;
+            i += 1;
+            j += 1;
+        }
+    }
+    count
+}
+
+/// A scanner that's been placed, with its beacons in global coordinates.
+struct Placed {
+    placement: Placement,
+    beacons: Vec<Coord>
+}
+
+impl Placed {
+    fn sees(&self, c: Coord, range: i32) -> bool {
+        let d = c - self.placement.position;
+        d.x.abs() <= range && d.y.abs() <= range && d.z.abs() <= range
+    }
+
+    /// How many of `other`'s beacons are in range of this scanner but
+    /// missing from its readings.
+    fn contradictions(&self, other: &Placed, range: i32) -> usize {
+        let own = self.beacons.iter().collect::<HashSet<_>>();
+        other.beacons.iter().filter(|b| self.sees(**b, range) && !own.contains(b)).count()
+    }
+}
+
+/// Finds every placement that lands at least `overlap` of `beacons` on the
+/// reference scanner's, within the contradictions the config tolerates.
+fn find_placements(reference: &Placed, beacons: &[Coord], config: &MatchConfig) -> Vec<Placement> {
+    let mut placements = vec![];
+    for orientation in Orientation::all() {
+        let rotated = beacons.iter().map(|b| orientation.apply(*b)).collect::<Vec<_>>();
+
+        let mut votes = HashMap::new();
+        for a in &reference.beacons {
+            for b in &rotated {
+                *votes.entry(*a - *b).or_insert(0) += 1;
+            }
+        }
+
+        let mut positions = votes.into_iter()
+            .filter(|(_, count)| *count >= config.overlap)
+            .map(|(position, _)| position)
+            .collect::<Vec<_>>();
+        positions.sort_unstable();
+
+        for position in positions {
+            let placement = Placement { position, orientation };
+            let candidate = Placed { placement, beacons: rotated.iter().map(|b| *b + position).collect() };
+            if reference.contradictions(&candidate, config.range) <= config.tolerance
+                && candidate.contradictions(reference, config.range) <= config.tolerance {
+                placements.push(placement);
+            }
+        }
+    }
+    placements
+}
+
+/// Places every scanner relative to scanner 0, working outwards from the
+/// scanners already placed.
+pub fn align(scanners: &[Scanner], config: &MatchConfig) -> Result<Alignment, AlignError> {
+    let fingerprints = scanners.iter().map(|s| fingerprint(&s.beacons)).collect::<Vec<_>>();
+    let needed = config.overlap * config.overlap.saturating_sub(1) / 2;
+
+    let mut placed: Vec<Option<Placed>> = scanners.iter().map(|_| None).collect();
+    let mut queue = VecDeque::new();
+    if let Some(first) = scanners.first() {
+        let placement = Placement { position: Coord::new(0, 0, 0), orientation: Orientation::IDENTITY };
+        placed[0] = Some(Placed { placement, beacons: first.beacons.clone() });
+        queue.push_back(0);
+    }
+
+    while let Some(i) = queue.pop_front() {
+        for j in 0..scanners.len() {
+            if placed[j].is_some() || shared(&fingerprints[i], &fingerprints[j]) < needed {
+                continue;
+            }
+
+            let reference = placed[i].as_ref().expect("queued scanners are placed");
+            let placements = find_placements(reference, &scanners[j].beacons, config);
+            match placements[..] {
+                [] => {},
+                [placement] => {
+                    let beacons = scanners[j].beacons.iter().map(|b| placement.apply(*b)).collect();
+                    placed[j] = Some(Placed { placement, beacons });
+                    queue.push_back(j);
+                },
+                _ => return Err(AlignError::Ambiguous { scanner: j, reference: i, placements })
+            }
+        }
+    }
+
+    let unaligned = (0..scanners.len()).filter(|i| placed[*i].is_none()).collect::<Vec<_>>();
+    if !unaligned.is_empty() {
+        return Err(AlignError::Unaligned(unaligned));
+    }
+
+    let mut beacons = BTreeMap::new();
+    let mut placements = vec![];
+    for (i, placed) in placed.into_iter().flatten().enumerate() {
+        for beacon in placed.beacons {
+            beacons.entry(beacon).or_insert(i);
+        }
+        placements.push(placed.placement);
+    }
+
+    let (beacons, seen_by) = beacons.into_iter().unzip();
+    Ok(Alignment { placements, beacons, seen_by })
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+    use aoc_utils::read_file;
+    use crate::parse_scanners;
+    use super::*;
+
+    #[test]
+    fn test_all_orientations_are_distinct_rotations() {
+        let orientations = Orientation::all();
+        assert_eq!(24, orientations.len());
+        assert_eq!(Orientation::IDENTITY, orientations[0]);
+
+        let p = Coord::new(1, 2, 3);
+        let images = orientations.iter().map(|o| o.apply(p)).collect::<HashSet<_>>();
+        assert_eq!(24, images.len());
+
+        // A rotation keeps handedness: x cross y must still be z.
+        for o in &orientations {
+            let (x, y, z) = (o.apply(Coord::new(1, 0, 0)), o.apply(Coord::new(0, 1, 0)), o.apply(Coord::new(0, 0, 1)));
+            assert_eq!(z, Coord::new(x.y * y.z - x.z * y.y, x.z * y.x - x.x * y.z, x.x * y.y - x.y * y.x));
+        }
+    }
+
+    #[test]
+    fn test_orientation_display() {
+        assert_eq!("(+x, +y, +z)", Orientation::IDENTITY.to_string());
+        assert_eq!("(-y, +x, +z)", Orientation { axes: [1, 0, 2], signs: [-1, 1, 1] }.to_string());
+    }
+
+    #[test]
+    fn test_fingerprint_ignores_placement() {
+        let beacons = vec![Coord::new(1, 2, 3), Coord::new(-4, 0, 7), Coord::new(5, 5, -5)];
+        let placement = Placement { position: Coord::new(100, -20, 3), orientation: Orientation::all()[17] };
+        let moved = beacons.iter().map(|b| placement.apply(*b)).collect::<Vec<_>>();
+
+        assert_eq!(vec![45, 89, 250], fingerprint(&beacons));
+        assert_eq!(fingerprint(&beacons), fingerprint(&moved));
+        assert_eq!(2, shared(&[1, 4, 4, 9], &[4, 4, 5]));
+    }
+
+    #[test]
+    fn test_align_example() -> Result<(), Box<dyn Error>> {
+        let scanners = parse_scanners(read_file("./inputs/day_19_test.in")?)?;
+        let alignment = align(&scanners, &MatchConfig::default())?;
+
+        let positions = alignment.placements.iter().map(|p| p.position).collect::<Vec<_>>();
+        assert_eq!(vec![
+            Coord::new(0, 0, 0), Coord::new(68, -1246, -43), Coord::new(1105, -1205, 1229),
+            Coord::new(-92, -2380, -20), Coord::new(-20, -1133, 1061)
+        ], positions);
+        assert_eq!(79, alignment.beacons.len());
+        assert!(alignment.beacons.contains(&Coord::new(-892, 524, 684)));
+        assert!(alignment.beacons.contains(&Coord::new(459, -707, 401)));
+        assert_eq!(0, alignment.seen_by[alignment.beacons.binary_search(&Coord::new(459, -707, 401)).unwrap()]);
+        assert_eq!(79, alignment.seen_by.len());
+
+        // Every scanner's own readings land on the global map.
+        for (scanner, placement) in scanners.iter().zip(&alignment.placements) {
+            assert!(scanner.beacons.iter().all(|b| alignment.beacons.binary_search(&placement.apply(*b)).is_ok()));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_align_reports_isolated_scanners() -> Result<(), Box<dyn Error>> {
+        let mut scanners = parse_scanners(read_file("./inputs/day_19_test.in")?)?;
+        scanners.push(Scanner { beacons: vec![Coord::new(5000, 5000, 5000)] });
+
+        let error = align(&scanners, &MatchConfig::default()).err().unwrap();
+        assert_eq!(AlignError::Unaligned(vec![5]), error);
+        assert_eq!("scanners 5 don't overlap with any placed scanner", error.to_string());
+        Ok(())
+    }
+
+    fn noisy_example() -> Result<Vec<Scanner>, Box<dyn Error>> {
+        let mut scanners = parse_scanners(read_file("./inputs/day_19_test.in")?)?;
+        // Scanner 1 misses a beacon it shares with scanner 0, and scanner 4
+        // reads one that isn't there, inside scanner 1's range.
+        scanners[1].beacons.retain(|b| *b != Coord::new(686, 422, 578));
+        scanners[4].beacons.push(Coord::new(-552, -44, 57));
+        Ok(scanners)
+    }
+
+    #[test]
+    fn test_noise_breaks_exact_matching() -> Result<(), Box<dyn Error>> {
+        let scanners = noisy_example()?;
+        assert_eq!(Err(AlignError::Unaligned(vec![1, 2, 3, 4])), align(&scanners, &MatchConfig::default()).map(|_| ()));
+
+        let config = MatchConfig { overlap: 11, ..MatchConfig::default() };
+        assert_eq!(Err(AlignError::Unaligned(vec![1, 2, 3, 4])), align(&scanners, &config).map(|_| ()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_tolerates_dropped_and_extra_readings() -> Result<(), Box<dyn Error>> {
+        let exact = align(&parse_scanners(read_file("./inputs/day_19_test.in")?)?, &MatchConfig::default())?;
+        let config = MatchConfig { overlap: 11, tolerance: 1, ..MatchConfig::default() };
+        let noisy = align(&noisy_example()?, &config)?;
+
+        assert_eq!(exact.placements, noisy.placements);
+        assert_eq!(80, noisy.beacons.len());
+        assert!(noisy.beacons.contains(&Coord::new(24, -1190, 509)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_reports_ambiguous_matches() {
+        let pair = || Scanner { beacons: vec![Coord::new(0, 0, 0), Coord::new(4, 0, 0)] };
+        let config = MatchConfig { overlap: 2, ..MatchConfig::default() };
+
+        match align(&[pair(), pair()], &config) {
+            Err(AlignError::Ambiguous { scanner: 1, reference: 0, placements }) => {
+                assert_eq!(8, placements.len());
+                assert!(placements.contains(&Placement { position: Coord::new(0, 0, 0), orientation: Orientation::IDENTITY }));
+            },
+            other => panic!("expected an ambiguity, got {:?}", other.map(|_| ()))
+        }
+    }
+}
@@ -0,0 +1,187 @@
+use std::fmt::Write;
+use crate::Coord;
+use crate::align::Alignment;
+
+/// A colour for each scanner, stepping the hue by the golden angle so that
+/// neighbouring scanners never look alike.
+pub fn colour(scanner: usize) -> [u8; 3] {
+    let hue = (scanner as f64 * 137.508) % 360.0;
+    let (saturation, value) = (0.7, 0.95);
+
+    let c = value * saturation;
+    let x = c * (1.0 - ((hue / 60.0) % 2.0 - 1.0).abs());
+    let (r, g, b) = match (hue / 60.0) as u32 {
+        0 => (c, x, 0.0),
+        1 => (x, c, 0.0),
+        2 => (0.0, c, x),
+        3 => (0.0, x, c),
+        4 => (x, 0.0, c),
+        _ => (c, 0.0, x)
+    };
+    [r, g, b].map(|n| ((n + value - c) * 255.0).round() as u8)
+}
+
+/// An ASCII PLY point cloud: the beacons, each in the colour of the first
+/// scanner to read it, followed by the scanners themselves.
+pub fn to_ply(alignment: &Alignment) -> String {
+    let mut out = String::new();
+    let vertices = alignment.beacons.len() + alignment.placements.len();
+
+    writeln!(out, "ply").unwrap();
+    writeln!(out, "format ascii 1.0").unwrap();
+    writeln!(out, "comment {} beacons then {} scanners", alignment.beacons.len(), alignment.placements.len()).unwrap();
+    writeln!(out, "element vertex {}", vertices).unwrap();
+    for property in ["int x", "int y", "int z", "uchar red", "uchar green", "uchar blue"] {
+        writeln!(out, "property {}", property).unwrap();
+    }
+    writeln!(out, "end_header").unwrap();
+
+    for (beacon, scanner) in alignment.beacons.iter().zip(&alignment.seen_by) {
+        let [r, g, b] = colour(*scanner);
+        writeln!(out, "{} {} {} {} {} {}", beacon.x, beacon.y, beacon.z, r, g, b).unwrap();
+    }
+    for (i, placement) in alignment.placements.iter().enumerate() {
+        let ([r, g, b], p) = (colour(i), placement.position);
+        writeln!(out, "{} {} {} {} {} {}", p.x, p.y, p.z, r, g, b).unwrap();
+    }
+    out
+}
+
+/// A Wavefront OBJ with the same vertices as `to_ply`, using the common
+/// `v x y z r g b` colour extension, and a point group per scanner holding
+/// the scanner and the beacons it was first to read.
+pub fn to_obj(alignment: &Alignment) -> String {
+    let mut out = String::new();
+    let rgb = |scanner: usize| colour(scanner).map(|n| format!("{:.3}", n as f64 / 255.0)).join(" ");
+
+    writeln!(out, "# {} beacons then {} scanners", alignment.beacons.len(), alignment.placements.len()).unwrap();
+    for (beacon, scanner) in alignment.beacons.iter().zip(&alignment.seen_by) {
+        writeln!(out, "v {} {} {} {}", beacon.x, beacon.y, beacon.z, rgb(*scanner)).unwrap();
+    }
+    for (i, placement) in alignment.placements.iter().enumerate() {
+        let p = placement.position;
+        writeln!(out, "v {} {} {} {}", p.x, p.y, p.z, rgb(i)).unwrap();
+    }
+
+    for i in 0..alignment.placements.len() {
+        // OBJ indices start at 1.
+        let points = (0..alignment.beacons.len())
+            .filter(|j| alignment.seen_by[*j] == i)
+            .chain([alignment.beacons.len() + i])
+            .map(|j| (j + 1).to_string())
+            .collect::<Vec<_>>();
+        writeln!(out, "g scanner_{}", i).unwrap();
+        writeln!(out, "p {}", points.join(" ")).unwrap();
+    }
+    out
+}
+
+/// Which way to look at the map. y is up: `Front` shows x across and y up,
+/// `Side` shows z across and y up, and `Top` shows x across and z up.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum View {
+    Top,
+    Side,
+    Front
+}
+
+impl View {
+    fn flatten(&self, c: Coord) -> (i64, i64) {
+        match self {
+            View::Top => (c.x as i64, c.z as i64),
+            View::Side => (c.z as i64, c.y as i64),
+            View::Front => (c.x as i64, c.y as i64)
+        }
+    }
+}
+
+/// Squeezes the map into a `width` by `height` grid of characters. Beacons
+/// are `#` and scanners are their number in base 36, drawn over beacons.
+pub fn project(alignment: &Alignment, view: View, width: usize, height: usize) -> String {
+    let beacons = alignment.beacons.iter().map(|b| view.flatten(*b)).collect::<Vec<_>>();
+    let scanners = alignment.placements.iter().map(|p| view.flatten(p.position)).collect::<Vec<_>>();
+    let all = beacons.iter().chain(&scanners);
+
+    let (Some(min_a), Some(max_a)) = (all.clone().map(|p| p.0).min(), all.clone().map(|p| p.0).max()) else {
+        return String::new();
+    };
+    let min_b = all.clone().map(|p| p.1).min().unwrap_or(0);
+    let max_b = all.map(|p| p.1).max().unwrap_or(0);
+
+    let (width, height) = (width.max(1), height.max(1));
+    let cell = |(a, b): (i64, i64)| {
+        let col = (a - min_a) * (width as i64 - 1) / (max_a - min_a).max(1);
+        let row = (max_b - b) * (height as i64 - 1) / (max_b - min_b).max(1);
+        (row as usize, col as usize)
+    };
+
+    let mut grid = vec![vec!['.'; width]; height];
+    for point in beacons {
+        let (row, col) = cell(point);
+        grid[row][col] = '#';
+    }
+    for (i, point) in scanners.into_iter().enumerate() {
+        let (row, col) = cell(point);
+        grid[row][col] = char::from_digit(i as u32, 36).unwrap_or('@');
+    }
+
+    grid.into_iter().map(|row| row.into_iter().collect::<String>()).collect::<Vec<_>>().join("\n")
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+    use crate::align::{Orientation, Placement};
+    use super::*;
+
+    fn small() -> Alignment {
+        let at = |x, y, z| Placement { position: Coord::new(x, y, z), orientation: Orientation::IDENTITY };
+        Alignment {
+            placements: vec![at(0, 0, 0), at(1, 0, -3)],
+            beacons: vec![Coord::new(-2, 0, 1), Coord::new(2, 2, 2)],
+            seen_by: vec![0, 1]
+        }
+    }
+
+    #[test]
+    fn test_colours_are_distinct() {
+        let colours = (0..36).map(colour).collect::<HashSet<_>>();
+        assert_eq!(36, colours.len());
+        assert_eq!([242, 73, 73], colour(0));
+    }
+
+    #[test]
+    fn test_ply() {
+        let ply = to_ply(&small());
+        let lines = ply.lines().collect::<Vec<_>>();
+        assert_eq!("ply", lines[0]);
+        assert!(lines.contains(&"element vertex 4"));
+
+        let body = &lines[lines.iter().position(|l| *l == "end_header").unwrap() + 1..];
+        assert_eq!(4, body.len());
+        assert_eq!("-2 0 1 242 73 73", body[0]);
+        assert_eq!(format!("1 0 -3 {} {} {}", colour(1)[0], colour(1)[1], colour(1)[2]), body[3]);
+    }
+
+    #[test]
+    fn test_obj() {
+        let obj = to_obj(&small());
+        let lines = obj.lines().collect::<Vec<_>>();
+        assert_eq!("v -2 0 1 0.949 0.286 0.286", lines[1]);
+        assert_eq!(4, lines.iter().filter(|l| l.starts_with("v ")).count());
+        assert_eq!(["g scanner_0", "p 1 3", "g scanner_1", "p 2 4"], lines[5..]);
+    }
+
+    #[test]
+    fn test_project() {
+        let alignment = Alignment { placements: vec![small().placements[0]], ..small() };
+        assert_eq!("....#\n.....\n#.0..", project(&alignment, View::Front, 5, 3));
+        assert_eq!("..#\n...\n...\n0#.", project(&alignment, View::Side, 3, 4));
+    }
+
+    #[test]
+    fn test_project_draws_scanners_over_beacons() {
+        let alignment = Alignment { beacons: vec![Coord::new(0, 0, 0)], seen_by: vec![0], ..small() };
+        assert_eq!("0.1", project(&alignment, View::Top, 3, 1));
+    }
+}
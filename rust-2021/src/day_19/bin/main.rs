@@ -1,61 +1,172 @@
+use std::{env, fs, mem};
 use std::error::Error;
-use aoc_utils::{Puzzle, run_all};
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::ops::{Add, Sub};
+use aoc_utils::{Puzzle, read_file, run_all};
+use crate::align::{Alignment, MatchConfig, align};
+use crate::export::{View, project, to_obj, to_ply};
+
+mod align;
+mod export;
 
 fn main() -> Result<(), Box<dyn Error>> {
-    run_all("./rust-2021/inputs/day_19.in", Box::new(Day19))
+    let args = env::args().collect::<Vec<_>>();
+    let command = args.get(1).map(|s| s.as_str());
+    if command.is_none() {
+        return run_all("./rust-2021/inputs/day_19.in", Box::new(Day19(MatchConfig::default())));
+    }
+
+    let alignment = Day19(MatchConfig::default()).parse(read_file("./rust-2021/inputs/day_19.in")?)?;
+    match (command, args.get(2)) {
+        (Some("scanners"), _) => {
+            for (i, placement) in alignment.placements.iter().enumerate() {
+                println!("scanner {}: at {} facing {}", i, placement.position, placement.orientation);
+            }
+        },
+        (Some("ply"), Some(path)) => fs::write(path, to_ply(&alignment))?,
+        (Some("obj"), Some(path)) => fs::write(path, to_obj(&alignment))?,
+        (Some("view"), _) => {
+            for (name, view) in [("top", View::Top), ("side", View::Side), ("front", View::Front)] {
+                println!("{}:\n{}\n", name, project(&alignment, view, 100, 40));
+            }
+        },
+        _ => return Err("usage: day_19 [scanners | ply <path> | obj <path> | view]".into())
+    }
+    Ok(())
 }
 
-struct Day19;
+struct Day19(MatchConfig);
 
-type Input = Vec<Scanner>;
+type Input = Alignment;
 type Output = i32;
 
-#[derive(Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
 struct Coord { x: i32, y: i32, z: i32 }
 
+impl Coord {
+    fn new(x: i32, y: i32, z: i32) -> Self {
+        Coord { x, y, z }
+    }
+
+    fn manhattan(&self, other: &Coord) -> i32 {
+        (self.x - other.x).abs() + (self.y - other.y).abs() + (self.z - other.z).abs()
+    }
+}
+
+impl Add for Coord {
+    type Output = Coord;
+
+    fn add(self, rhs: Coord) -> Coord {
+        Coord::new(self.x + rhs.x, self.y + rhs.y, self.z + rhs.z)
+    }
+}
+
+impl Sub for Coord {
+    type Output = Coord;
+
+    fn sub(self, rhs: Coord) -> Coord {
+        Coord::new(self.x - rhs.x, self.y - rhs.y, self.z - rhs.z)
+    }
+}
+
+impl Display for Coord {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{},{},{}", self.x, self.y, self.z)
+    }
+}
+
 struct Scanner {
     beacons: Vec<Coord>
 }
 
-impl Puzzle<Input, Output> for Day19 {
-    fn parse(&self, contents: Vec<String>) -> Result<Input, Box<dyn Error>> {
-        let mut scanners = vec![];
-        let mut coords = vec![];
-
-        for line in contents {
-            if line.is_empty() {
-                scanners.push(Scanner { beacons: coords.clone() });
-            } else if line.starts_with("---") {
-                coords = vec![];
-            } else {
-                let split = line.split(',')
-                    .map(|s| s.parse::<i32>().unwrap())
-                    .collect::<Vec<_>>();
-
-                coords.push(Coord { x: split[0], y: split[1], z: split[2] });
+fn parse_scanners(contents: Vec<String>) -> Result<Vec<Scanner>, Box<dyn Error>> {
+    let mut scanners = vec![];
+    let mut coords = vec![];
+
+    for line in contents {
+        if line.is_empty() {
+            if !coords.is_empty() {
+                scanners.push(Scanner { beacons: mem::take(&mut coords) });
+            }
+        } else if line.starts_with("---") {
+            coords = vec![];
+        } else {
+            let split = line.split(',')
+                .map(|s| s.parse::<i32>())
+                .collect::<Result<Vec<_>, _>>()?;
+            if split.len() != 3 {
+                return Err(format!("expected x,y,z but got {:?}", line).into());
             }
-        }
 
-        if !coords.is_empty() {
-            scanners.push(Scanner { beacons: coords.clone() });
+            coords.push(Coord { x: split[0], y: split[1], z: split[2] });
         }
+    }
 
-        Ok(scanners)
+    if !coords.is_empty() {
+        scanners.push(Scanner { beacons: coords });
+    }
+
+    Ok(scanners)
+}
+
+impl Puzzle<Input, Output> for Day19 {
+    fn parse(&self, contents: Vec<String>) -> Result<Input, Box<dyn Error>> {
+        Ok(align(&parse_scanners(contents)?, &self.0)?)
     }
 
     fn calculate_part_1(&self, input: &Input) -> Output {
-        todo!()
+        input.beacons.len() as Output
     }
 
     fn calculate_part_2(&self, input: &Input) -> Output {
-        todo!()
+        let positions = input.placements.iter().map(|p| p.position).collect::<Vec<_>>();
+        positions.iter()
+            .flat_map(|a| positions.iter().map(move |b| a.manhattan(b)))
+            .max()
+            .unwrap_or(0)
     }
 }
 
 #[cfg(test)]
 mod test {
-
+    use aoc_utils::{run_part_one, run_part_two};
     use super::*;
 
+    #[test]
+    fn test_parse_scanners() -> Result<(), Box<dyn Error>> {
+        let scanners = parse_scanners(read_file("./inputs/day_19_test.in")?)?;
+        assert_eq!(5, scanners.len());
+        assert_eq!(25, scanners[0].beacons.len());
+        assert_eq!(Coord::new(404, -588, -901), scanners[0].beacons[0]);
+        assert!(parse_scanners(vec!["--- scanner 0 ---".to_string(), "1,2".to_string()]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_scanners_trailing_blank_lines() -> Result<(), Box<dyn Error>> {
+        let mut contents = read_file("./inputs/day_19_test.in")?;
+        contents.extend(["".to_string(), "".to_string()]);
+        let scanners = parse_scanners(contents)?;
+        assert_eq!(5, scanners.len());
+        assert_eq!(Coord::new(30, -46, -14), scanners[4].beacons[25]);
+        Ok(())
+    }
 
-}
\ No newline at end of file
+    #[test]
+    fn test_manhattan() {
+        assert_eq!(3621, Coord::new(1105, -1205, 1229).manhattan(&Coord::new(-92, -2380, -20)));
+    }
+
+    #[test]
+    fn test_calculate_one() -> Result<(), Box<dyn Error>> {
+        assert_eq!(79, run_part_one("./inputs/day_19_test.in", Box::new(Day19(MatchConfig::default())))?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_calculate_two() -> Result<(), Box<dyn Error>> {
+        assert_eq!(3621, run_part_two("./inputs/day_19_test.in", Box::new(Day19(MatchConfig::default())))?);
+        Ok(())
+    }
+}
@@ -1,6 +1,13 @@
+use std::env;
 use std::error::Error;
+use std::fmt;
+use std::fmt::{Display, Formatter};
 use std::fs::File;
 use std::io::{BufRead, BufReader};
+use std::str::FromStr;
+use crate::motion::{Aim, Direct, MotionModel, Position, run};
+
+mod motion;
 
 fn main() -> Result<(), Box<dyn Error>> {
     let file = File::open("./rust-2021/inputs/day_2_input")?;
@@ -11,60 +18,128 @@ fn main() -> Result<(), Box<dyn Error>> {
         contents.push(line?);
     }
 
-    let parsed_input = parse(contents);
+    let parsed_input = parse(contents)?;
 
-    calculate_part_1(&parsed_input);
-    calculate_part_2(&parsed_input);
+    let args = env::args().collect::<Vec<_>>();
+    match args.get(1).map(|s| s.as_str()) {
+        Some("trace") => match args.get(2).map(|s| s.as_str()) {
+            Some("direct") => print_trace(&Direct, &parsed_input),
+            Some("aim") | None => print_trace(&Aim, &parsed_input),
+            Some(other) => return Err(format!("unknown model {:?}, expected direct or aim", other).into())
+        },
+        _ => {
+            println!("{:?}", calculate_part_1(&parsed_input));
+            println!("{:?}", calculate_part_2(&parsed_input));
+        }
+    }
 
     Ok(())
 }
 
+/// Prints the dive path as CSV, then notes if the submarine ever went above
+/// the surface.
+fn print_trace<M: MotionModel>(model: &M, commands: &[Command]) {
+    let mut trace = vec![];
+    run(model, commands, Some(&mut trace));
+
+    println!("step,horizontal,depth");
+    for (step, position) in trace.iter().enumerate() {
+        println!("{},{},{}", step, position.horizontal, position.depth);
+    }
+    if let Some(step) = trace.iter().position(|p| p.depth < 0) {
+        eprintln!("above the surface after step {}", step);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
 enum Command {
-    Forward(i32),
-    Down(i32),
-    Up(i32)
+    Forward(i64),
+    Down(i64),
+    Up(i64)
+}
+
+#[derive(Debug, PartialEq)]
+enum ParseCommandError {
+    /// Not a verb and an amount separated by a space.
+    Malformed(String),
+    UnknownVerb(String),
+    BadAmount(String)
+}
+
+impl Display for ParseCommandError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseCommandError::Malformed(line) => write!(f, "expected a command and an amount in {:?}", line),
+            ParseCommandError::UnknownVerb(verb) => write!(f, "unknown command {:?}", verb),
+            ParseCommandError::BadAmount(amount) => write!(f, "{:?} isn't a whole number", amount)
+        }
+    }
+}
+
+impl Error for ParseCommandError {}
+
+impl FromStr for Command {
+    type Err = ParseCommandError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (verb, amount) = s.split_once(' ').ok_or_else(|| ParseCommandError::Malformed(s.to_string()))?;
+        let amount = amount.parse::<i64>().map_err(|_| ParseCommandError::BadAmount(amount.to_string()))?;
+
+        match verb {
+            "forward" => Ok(Command::Forward(amount)),
+            "down" => Ok(Command::Down(amount)),
+            "up" => Ok(Command::Up(amount)),
+            _ => Err(ParseCommandError::UnknownVerb(verb.to_string()))
+        }
+    }
 }
 
-fn parse(input: Vec<String>) -> Vec<Command> {
-    input.into_iter()
-        .map(|line| {
-            let l = line.split(" ").collect::<Vec<&str>>();
-            let cmd = l[0];
-            let amount = l[1].parse::<i32>().expect("can't parse");
-
-            match (cmd, amount) {
-                ("forward", l) => Some(Command::Forward(l)),
-                ("down", l) => Some(Command::Down(l)),
-                ("up", l) => Some(Command::Up(l)),
-                _ => None
-            }
-        })
-        .filter_map(|e| e)
+fn parse(input: Vec<String>) -> Result<Vec<Command>, Box<dyn Error>> {
+    input.iter()
+        .enumerate()
+        .map(|(i, line)| line.parse::<Command>().map_err(|e| format!("line {}: {}", i + 1, e).into()))
         .collect()
 }
 
-fn calculate_part_1(input: &Vec<Command>) {
-    let calc = input.iter()
-        .fold((0, 0), |(hoz, dep), command | {
-            match &command {
-                Command::Forward(u) => (hoz + u, dep),
-                Command::Down(u) => (hoz, dep + u),
-                Command::Up(u) => (hoz, dep - u),
-            }
-        });
-
-    println!("{:?}", calc.0 * calc.1);
+fn product(position: Position) -> i64 {
+    position.horizontal * position.depth
+}
+
+fn calculate_part_1(input: &[Command]) -> i64 {
+    product(run(&Direct, input, None))
 }
 
-fn calculate_part_2(input: &Vec<Command>) {
-    let calc = input.iter()
-        .fold((0, 0, 0), |(hoz, dep, aim), command | {
-            match &command {
-                Command::Down(u) => (hoz, dep, aim + u),
-                Command::Up(u) => (hoz, dep, aim - u),
-                Command::Forward(u) => (hoz + u, dep + aim * u, aim),
-            }
-        });
-
-    println!("{:?}", calc.0 * calc.1);
-}
\ No newline at end of file
+fn calculate_part_2(input: &[Command]) -> i64 {
+    product(run(&Aim, input, None))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn example() -> Vec<String> {
+        ["forward 5", "down 5", "forward 8", "up 3", "down 8", "forward 2"].iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_calculate_part_1() -> Result<(), Box<dyn Error>> {
+        assert_eq!(150, calculate_part_1(&parse(example())?));
+        Ok(())
+    }
+
+    #[test]
+    fn test_calculate_part_2() -> Result<(), Box<dyn Error>> {
+        assert_eq!(900, calculate_part_2(&parse(example())?));
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(Err(ParseCommandError::UnknownVerb("sideways".to_string())), "sideways 3".parse::<Command>());
+        assert_eq!(Err(ParseCommandError::BadAmount("x".to_string())), "up x".parse::<Command>());
+        assert_eq!(Err(ParseCommandError::Malformed("down".to_string())), "down".parse::<Command>());
+
+        let error = parse(vec!["up 1".to_string(), "back 2".to_string()]).unwrap_err();
+        assert_eq!("line 2: unknown command \"back\"", error.to_string());
+    }
+}
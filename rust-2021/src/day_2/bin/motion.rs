@@ -0,0 +1,147 @@
+use crate::Command;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Position {
+    pub horizontal: i64,
+    pub depth: i64
+}
+
+/// How the submarine responds to commands. `State` is whatever the model
+/// tracks besides the position, such as the aim.
+pub trait MotionModel {
+    type State: Clone;
+
+    fn initial(&self) -> Self::State;
+    fn step(&self, state: &Self::State, command: &Command) -> Self::State;
+    fn position(&self, state: &Self::State) -> Position;
+}
+
+/// Part 1: down and up change the depth directly.
+pub struct Direct;
+
+/// Part 2: down and up turn the submarine, and going forward dives by the
+/// aim times the distance.
+pub struct Aim;
+
+impl MotionModel for Direct {
+    type State = Position;
+
+    fn initial(&self) -> Position {
+        Position::default()
+    }
+
+    fn step(&self, state: &Position, command: &Command) -> Position {
+        let Position { horizontal, depth } = *state;
+        match command {
+            Command::Forward(u) => Position { horizontal: horizontal + u, depth },
+            Command::Down(u) => Position { horizontal, depth: depth + u },
+            Command::Up(u) => Position { horizontal, depth: depth - u }
+        }
+    }
+
+    fn position(&self, state: &Position) -> Position {
+        *state
+    }
+}
+
+impl MotionModel for Aim {
+    type State = (Position, i64);
+
+    fn initial(&self) -> Self::State {
+        (Position::default(), 0)
+    }
+
+    fn step(&self, (position, aim): &Self::State, command: &Command) -> Self::State {
+        match command {
+            Command::Forward(u) => (Position { horizontal: position.horizontal + u, depth: position.depth + aim * u }, *aim),
+            Command::Down(u) => (*position, aim + u),
+            Command::Up(u) => (*position, aim - u)
+        }
+    }
+
+    fn position(&self, (position, _): &Self::State) -> Position {
+        *position
+    }
+}
+
+/// A model that needs nothing but the position can be a plain function.
+impl<F: Fn(Position, &Command) -> Position> MotionModel for F {
+    type State = Position;
+
+    fn initial(&self) -> Position {
+        Position::default()
+    }
+
+    fn step(&self, state: &Position, command: &Command) -> Position {
+        self(*state, command)
+    }
+
+    fn position(&self, state: &Position) -> Position {
+        *state
+    }
+}
+
+/// Runs every command and returns where the submarine ends up. With a
+/// `trace`, the starting position and the one after each command are
+/// pushed onto it.
+pub fn run<M: MotionModel>(model: &M, commands: &[Command], mut trace: Option<&mut Vec<Position>>) -> Position {
+    let mut state = model.initial();
+    if let Some(trace) = trace.as_deref_mut() {
+        trace.push(model.position(&state));
+    }
+
+    for command in commands {
+        state = model.step(&state, command);
+        if let Some(trace) = trace.as_deref_mut() {
+            trace.push(model.position(&state));
+        }
+    }
+
+    model.position(&state)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn example() -> Vec<Command> {
+        vec![Command::Forward(5), Command::Down(5), Command::Forward(8), Command::Up(3), Command::Down(8), Command::Forward(2)]
+    }
+
+    #[test]
+    fn test_direct() {
+        assert_eq!(Position { horizontal: 15, depth: 10 }, run(&Direct, &example(), None));
+    }
+
+    #[test]
+    fn test_aim() {
+        assert_eq!(Position { horizontal: 15, depth: 60 }, run(&Aim, &example(), None));
+    }
+
+    #[test]
+    fn test_trace() {
+        let mut trace = vec![];
+        let end = run(&Aim, &example(), Some(&mut trace));
+
+        assert_eq!(7, trace.len());
+        assert_eq!(Position::default(), trace[0]);
+        assert_eq!(Position { horizontal: 13, depth: 40 }, trace[3]);
+        assert_eq!(Some(&end), trace.last());
+    }
+
+    #[test]
+    fn test_custom_model() {
+        // Up is capped at the surface.
+        let surfacing = |p: Position, command: &Command| match command {
+            Command::Up(u) => Position { depth: (p.depth - u).max(0), ..p },
+            _ => Direct.step(&p, command)
+        };
+
+        let commands = vec![Command::Down(2), Command::Up(5), Command::Forward(1), Command::Down(1)];
+        let mut trace = vec![];
+        assert_eq!(Position { horizontal: 1, depth: 1 }, run(&surfacing, &commands, Some(&mut trace)));
+        assert!(trace.iter().all(|p| p.depth >= 0));
+
+        assert_eq!(Position { horizontal: 1, depth: -2 }, run(&Direct, &commands, None));
+    }
+}
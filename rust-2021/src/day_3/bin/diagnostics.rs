@@ -0,0 +1,164 @@
+/// Decides which bit survives at a position, given how many of the rows
+/// still in the running have a 1 there and how many have a 0.
+pub trait Criterion {
+    fn keep(&self, ones: usize, zeros: usize) -> bool;
+}
+
+/// Keeps the more common bit, or `on_tie` when there are as many of each.
+pub struct MostCommon { pub on_tie: bool }
+
+/// Keeps the less common bit, or `on_tie` when there are as many of each.
+pub struct LeastCommon { pub on_tie: bool }
+
+impl Criterion for MostCommon {
+    fn keep(&self, ones: usize, zeros: usize) -> bool {
+        if ones == zeros { self.on_tie } else { ones > zeros }
+    }
+}
+
+impl Criterion for LeastCommon {
+    fn keep(&self, ones: usize, zeros: usize) -> bool {
+        if ones == zeros { self.on_tie } else { ones < zeros }
+    }
+}
+
+impl<F: Fn(usize, usize) -> bool> Criterion for F {
+    fn keep(&self, ones: usize, zeros: usize) -> bool {
+        self(ones, zeros)
+    }
+}
+
+type Bits = Vec<u64>;
+
+fn count(bits: &[u64]) -> usize {
+    bits.iter().map(|word| word.count_ones() as usize).sum()
+}
+
+/// A diagnostic report stored a column at a time: `columns[c]` holds bit `c`
+/// of every row, counting columns from the left, so a report can be any
+/// width.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostics {
+    rows: usize,
+    width: usize,
+    columns: Vec<Bits>
+}
+
+impl Diagnostics {
+    pub fn parse(lines: &[String]) -> Result<Self, String> {
+        let width = lines.first().map_or(0, |line| line.len());
+        let words = lines.len().div_ceil(64);
+        let mut columns = vec![vec![0; words]; width];
+
+        for (row, line) in lines.iter().enumerate() {
+            if line.len() != width {
+                return Err(format!("line {} has {} bits rather than {}", row + 1, line.len(), width));
+            }
+            for (column, c) in line.chars().enumerate() {
+                match c {
+                    '0' => {},
+                    '1' => columns[column][row / 64] |= 1 << (row % 64),
+                    _ => return Err(format!("line {} has {:?} where a bit should be", row + 1, c))
+                }
+            }
+        }
+
+        Ok(Diagnostics { rows: lines.len(), width, columns })
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn bit(&self, row: usize, column: usize) -> bool {
+        self.columns[column][row / 64] >> (row % 64) & 1 == 1
+    }
+
+    /// How many rows have a 1 in each column.
+    pub fn popcounts(&self) -> Vec<usize> {
+        self.columns.iter().map(|column| count(column)).collect()
+    }
+
+    /// Narrows the rows down column by column, left to right, keeping those
+    /// whose bit is the one `criterion` picks, until a single row is left.
+    /// Identical rows can't be told apart, so the first of them wins; `None`
+    /// means no row made it.
+    pub fn filter(&self, criterion: &impl Criterion) -> Option<usize> {
+        let mut alive = vec![u64::MAX; self.rows.div_ceil(64)];
+        if !self.rows.is_multiple_of(64) {
+            alive[self.rows / 64] = (1 << (self.rows % 64)) - 1;
+        }
+
+        for column in &self.columns {
+            let total = count(&alive);
+            if total <= 1 {
+                break;
+            }
+
+            let ones = alive.iter().zip(column).map(|(a, c)| (a & c).count_ones() as usize).sum::<usize>();
+            let keep = criterion.keep(ones, total - ones);
+            for (a, c) in alive.iter_mut().zip(column) {
+                *a &= if keep { *c } else { !c };
+            }
+        }
+
+        alive.iter().enumerate()
+            .find(|(_, word)| **word != 0)
+            .map(|(i, word)| i * 64 + word.trailing_zeros() as usize)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn example() -> Diagnostics {
+        let lines = "00100 11110 10110 10111 10101 01111 00111 11100 10000 11001 00010 01010".split(' ')
+            .map(String::from)
+            .collect::<Vec<_>>();
+        Diagnostics::parse(&lines).unwrap()
+    }
+
+    #[test]
+    fn test_popcounts() {
+        assert_eq!(vec![7, 5, 8, 7, 5], example().popcounts());
+    }
+
+    #[test]
+    fn test_filter() {
+        let diagnostics = example();
+        // 10111 and 01010
+        assert_eq!(Some(3), diagnostics.filter(&MostCommon { on_tie: true }));
+        assert_eq!(Some(11), diagnostics.filter(&LeastCommon { on_tie: false }));
+        assert_eq!(Some(3), diagnostics.filter(&|ones: usize, zeros: usize| ones >= zeros));
+        // always taking a 0 ends with 00010
+        assert_eq!(Some(10), diagnostics.filter(&|_: usize, _: usize| false));
+        assert_eq!(None, Diagnostics::parse(&[]).unwrap().filter(&MostCommon { on_tie: true }));
+    }
+
+    #[test]
+    fn test_wide_rows() {
+        // 100 rows of 130 bits; row i has its single 1 in column i.
+        let lines = (0..100)
+            .map(|i| (0..130).map(|c| if c == i { '1' } else { '0' }).collect::<String>())
+            .collect::<Vec<_>>();
+        let diagnostics = Diagnostics::parse(&lines).unwrap();
+
+        assert_eq!(130, diagnostics.width());
+        assert!(diagnostics.bit(99, 99) && !diagnostics.bit(99, 98));
+        assert_eq!(100, diagnostics.popcounts().iter().sum::<usize>());
+        assert_eq!(Some(0), diagnostics.filter(&LeastCommon { on_tie: false }));
+        assert_eq!(Some(99), diagnostics.filter(&MostCommon { on_tie: false }));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let lines = |s: &[&str]| s.iter().map(|l| l.to_string()).collect::<Vec<_>>();
+        assert_eq!(Err("line 2 has 2 bits rather than 3".to_string()), Diagnostics::parse(&lines(&["010", "01"])));
+        assert_eq!(Err("line 1 has '2' where a bit should be".to_string()), Diagnostics::parse(&lines(&["012"])));
+    }
+}
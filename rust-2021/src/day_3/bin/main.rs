@@ -1,6 +1,10 @@
 use std::error::Error;
 use std::fs::File;
 use std::io::{BufRead, BufReader};
+use aoc_utils::bigint::BigUint;
+use crate::diagnostics::{Criterion, Diagnostics, LeastCommon, MostCommon};
+
+mod diagnostics;
 
 fn main() -> Result<(), Box<dyn Error>> {
     let file = File::open("./rust-2021/inputs/day_3_input")?;
@@ -13,85 +17,77 @@ fn main() -> Result<(), Box<dyn Error>> {
 
     let parsed_input = parse(contents)?;
 
-    calculate_part_1(&parsed_input);
-    calculate_part_2(&parsed_input);
+    println!("{}", calculate_part_1(&parsed_input));
+    println!("{}", calculate_part_2(&parsed_input)?);
 
     Ok(())
 }
 
-#[derive(Copy, Clone, Debug)]
-struct Reading {
-    val: isize,
-    size: usize
+fn parse(input: Vec<String>) -> Result<Diagnostics, Box<dyn Error>> {
+    Ok(Diagnostics::parse(&input)?)
 }
 
-fn parse(input: Vec<String>) -> Result<Vec<Reading>, Box<dyn Error>> {
-    let mut results = vec![];
-    for l in input {
-        results.push(Reading {
-            val: isize::from_str_radix(&l[..], 2)?,
-            size: l.len()
-        })
-    }
-    Ok(results)
+/// Reads bits as a binary number, most significant first. Reports can be
+/// wider than any primitive integer.
+fn to_number(bits: impl Iterator<Item = bool>) -> BigUint {
+    bits.fold(BigUint::zero(), |n, bit| n * BigUint::from(2) + BigUint::from(bit as u64))
 }
 
-fn calculate_part_1(input: &Vec<Reading>) {
-    let mut readings = vec![0; input[0].size];
-
-    // Count bits
-    for reading in input {
-        let mut g = reading.val.clone();
-        for i in 0..reading.size {
-            if g & 1 == 1 {
-                readings[i] += 1;
-            }
+fn calculate_part_1(input: &Diagnostics) -> BigUint {
+    let gamma = input.popcounts().into_iter()
+        .map(|ones| MostCommon { on_tie: true }.keep(ones, input.rows() - ones))
+        .collect::<Vec<_>>();
 
-            g = g >> 1;
-        }
-    }
+    let g = to_number(gamma.iter().copied());
+    let e = to_number(gamma.iter().map(|bit| !bit));
+    g * e
+}
 
-    let total = input.len();
-    let (g, e) = readings.into_iter().enumerate()
-        .fold((0, 0), |(g, e), (i, n) | {
-            if n >= total - n {
-                (g + (1 << i), e)
-            } else {
-                (g, e + (1 << i))
-            }
-        });
-
-    println!("{:?}", g * e);
+fn rating(input: &Diagnostics, criterion: &impl Criterion) -> Result<BigUint, Box<dyn Error>> {
+    let row = input.filter(criterion).ok_or("no rows to rate")?;
+    Ok(to_number((0..input.width()).map(|column| input.bit(row, column))))
 }
 
-fn find(input: &Vec<Reading>, cursor: usize, common: bool) -> Vec<Reading> {
-    if input.len() == 1 { return input.clone(); }
+fn calculate_part_2(input: &Diagnostics) -> Result<BigUint, Box<dyn Error>> {
+    let oxygen = rating(input, &MostCommon { on_tie: true })?;
+    let co2 = rating(input, &LeastCommon { on_tie: false })?;
 
-    let count = input.into_iter()
-        .filter(|reading| (reading.val >> cursor) & 1 == 1)
-        .count();
+    Ok(oxygen * co2)
+}
 
-    let keep = if count >= (input.len() - count) {
-        if common { 1 } else { 0 }
-    } else {
-        if common { 0 } else { 1 }
-    };
+#[cfg(test)]
+mod test {
+    use std::io::Read;
+    use super::*;
 
-    input.into_iter().cloned()
-        .filter(|reading| ((reading.val >> cursor) & 1) ^ keep == 0)
-        .collect()
-}
+    fn example() -> Result<Diagnostics, Box<dyn Error>> {
+        let mut contents = String::new();
+        File::open("./inputs/day_3_test_input")?.read_to_string(&mut contents)?;
+        parse(contents.lines().map(String::from).collect())
+    }
 
-fn calculate_part_2(input: &Vec<Reading>) {
-    let mut oxygen = input.clone();
-    for i in (0..input[0].size).rev() {
-        oxygen = find(&oxygen, i, true);
+    #[test]
+    fn test_calculate_part_1() -> Result<(), Box<dyn Error>> {
+        assert_eq!(BigUint::from(198), calculate_part_1(&example()?));
+        Ok(())
     }
 
-    let mut co2 = input.clone();
-    for i in (0..input[0].size).rev() {
-        co2 = find(&co2, i, false);
+    #[test]
+    fn test_calculate_part_2() -> Result<(), Box<dyn Error>> {
+        assert_eq!(BigUint::from(230), calculate_part_2(&example()?)?);
+        Ok(())
     }
 
-    println!("{:?}", co2[0].val * oxygen[0].val);
-}
\ No newline at end of file
+    #[test]
+    fn test_wider_than_64_bits() -> Result<(), Box<dyn Error>> {
+        // 1 followed by 69 zeros is 2^69.
+        let lines = vec![format!("1{}", "0".repeat(69)), format!("1{}", "0".repeat(69)), "0".repeat(70)];
+        let input = parse(lines)?;
+
+        let power = (0..69).map(|_| BigUint::from(2)).product::<BigUint>();
+        assert_eq!("590295810358705651712", power.to_string());
+        assert_eq!(power.clone(), rating(&input, &MostCommon { on_tie: true })?);
+        assert_eq!(BigUint::zero(), rating(&input, &LeastCommon { on_tie: false })?);
+        Ok(())
+    }
+}
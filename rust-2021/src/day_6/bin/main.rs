@@ -1,81 +1,74 @@
-use std::collections::HashMap;
+use std::env;
 use std::error::Error;
+use aoc_utils::matrix::{Exact, Modulo, Ring};
 use aoc_utils::read_file;
+use crate::population::{Lifecycle, to_csv};
+
+mod population;
 
 fn main() -> Result<(), Box<dyn Error>> {
     let contents = read_file("./rust-2021/inputs/day_6_input")?;
     let parsed_input = parse(contents)?;
 
-    println!("{:?}", calculate_part_one(&parsed_input));
-    println!("{:?}", calculate_part_two(&parsed_input));
+    let mut args = env::args().collect::<Vec<_>>();
+    let lifecycle = match args.iter().position(|a| a == "--lifecycle") {
+        Some(i) => {
+            let usage = "usage: --lifecycle <reset>,<newborn>";
+            let spec = args.get(i + 1).ok_or(usage)?.clone();
+            args.drain(i..i + 2);
+            let (reset, newborn) = spec.split_once(',').ok_or(usage)?;
+            Lifecycle::new(reset.parse()?, newborn.parse()?).ok_or("the reset timer can't be past the newborn timer")?
+        },
+        None => Lifecycle::default()
+    };
+    match args.get(1).map(|s| s.as_str()) {
+        Some("csv") => {
+            let days = args.get(2).ok_or("usage: day_6 csv <days>")?.parse()?;
+            let counts = lifecycle.histogram(&parsed_input, &Exact)?;
+            print!("{}", to_csv(&lifecycle.series(&counts, days, &Exact), &Exact));
+        },
+        Some("jump") => {
+            let days = args.get(2).ok_or("usage: day_6 jump <days> [prime]")?.parse()?;
+            match args.get(3) {
+                Some(p) => {
+                    let ring = Modulo::new(p.parse()?).ok_or("the modulus must be at least 2")?;
+                    println!("{}", ring.sum(&lifecycle.jump(&lifecycle.histogram(&parsed_input, &ring)?, days, &ring)));
+                },
+                None => println!("{}", Exact.sum(&lifecycle.jump(&lifecycle.histogram(&parsed_input, &Exact)?, days, &Exact)))
+            }
+        },
+        _ => {
+            println!("{:?}", calculate_part_one(&parsed_input, &lifecycle)?);
+            println!("{:?}", calculate_part_two(&parsed_input, &lifecycle)?);
+        }
+    }
 
     Ok(())
 }
 
-type ParsedInput = Vec<Fish>;
+type ParsedInput = Vec<usize>;
 type Solution = u64;
 
-#[derive(Clone, Eq, Hash)]
-struct Fish {
-    birth: u64,
-    timer: u64
-}
+fn parse(contents: Vec<String>) -> Result<ParsedInput, Box<dyn Error>> {
+    let timers = contents[0].split(',')
+        .map(|l| l.parse::<usize>())
+        .collect::<Result<Vec<_>, _>>()?;
 
-impl PartialEq for Fish {
-    fn eq(&self, other: &Fish) -> bool {
-        self.birth == other.birth && self.timer == other.timer
-    }
+    Ok(timers)
 }
 
-impl Fish {
-    fn cycle(&self, max_days: u64, memo: &mut HashMap<Fish, u64>) -> u64 {
-        if memo.contains_key(self) {
-            return memo[&self];
-        }
-
-        let mut timer = self.timer;
-        let mut results = 1;
-        for i in self.birth..max_days {
-            if timer == 0 {
-                let new_fish = Fish { birth: i+1, timer: 8 };
-                results += new_fish.cycle(max_days, memo);
-
-                timer = 6;
-            } else {
-                timer -= 1;
-            }
-        }
+fn count_after(input: &ParsedInput, lifecycle: &Lifecycle, days: u64) -> Result<Solution, Box<dyn Error>> {
+    let counts = lifecycle.histogram(input, &Exact)?;
 
-        memo.insert(self.clone(), results);
-        results
-    }
+    Ok(Exact.sum(&lifecycle.jump(&counts, days, &Exact)).to_u64().ok_or("too many fish for a u64")?)
 }
 
-fn parse(contents: Vec<String>) -> Result<ParsedInput, Box<dyn Error>> {
-    Ok(contents[0].split(',')
-        .map(|l| l.parse::<u64>().unwrap())
-        .map(|timer| Fish { birth: 0, timer })
-        .collect::<Vec<Fish>>())
+fn calculate_part_one(input: &ParsedInput, lifecycle: &Lifecycle) -> Result<Solution, Box<dyn Error>> {
+    count_after(input, lifecycle, 80)
 }
 
-fn calculate_part_one(input: &ParsedInput) -> Solution {
-    let mut memo = HashMap::new();
-    let mut count = 0;
-    for fish in input {
-        count += fish.cycle(80, &mut memo);
-    }
-
-    count
-}
-
-fn calculate_part_two(input: &ParsedInput) -> Solution {
-    let mut memo = HashMap::new();
-    let mut count = 0;
-    for fish in input {
-        count += fish.cycle(256, &mut memo);
-    }
-
-    count
+fn calculate_part_two(input: &ParsedInput, lifecycle: &Lifecycle) -> Result<Solution, Box<dyn Error>> {
+    count_after(input, lifecycle, 256)
 }
 
 #[cfg(test)]
@@ -88,7 +81,7 @@ mod test {
         let contents = read_file("./inputs/day_6_test_input")?;
         let input = parse(contents)?;
 
-        assert_eq!(5934, calculate_part_one(&input));
+        assert_eq!(5934, calculate_part_one(&input, &Lifecycle::default())?);
 
         Ok(())
     }
@@ -98,8 +91,33 @@ mod test {
         let contents = read_file("./inputs/day_6_test_input")?;
         let input = parse(contents)?;
 
-        assert_eq!(26984457539, calculate_part_two(&input));
+        assert_eq!(26984457539, calculate_part_two(&input, &Lifecycle::default())?);
 
         Ok(())
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_other_lifecycle() -> Result<(), Box<dyn Error>> {
+        let input = parse(read_file("./inputs/day_6_test_input")?)?;
+        // every fish spawns once within a few days, then neither it nor its
+        // young spawn again for 500
+        let lifecycle = Lifecycle::new(500, 500).unwrap();
+        assert_eq!(10, calculate_part_one(&input, &lifecycle)?);
+        assert_eq!(10, calculate_part_two(&input, &lifecycle)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_bad_timers() -> Result<(), Box<dyn Error>> {
+        assert!(parse(vec!["3,4,x".to_string()]).is_err());
+
+        // Timers past 8 are up to the lifecycle, not the parser.
+        let input = parse(vec!["3,10".to_string()])?;
+        assert_eq!("timer 10 is past the newborn timer 8", calculate_part_one(&input, &Lifecycle::default()).unwrap_err().to_string());
+        let lifecycle = Lifecycle::new(6, 12).unwrap();
+        assert_eq!(13, lifecycle.histogram(&input, &Exact)?.len());
+        assert!(calculate_part_one(&input, &lifecycle).is_ok());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,179 @@
+use std::fmt::Display;
+use aoc_utils::matrix::{Matrix, Ring};
+
+/// How a fish's timer runs. A fish whose timer is at 0 spawns the next day,
+/// going back to `reset` while the newborn starts at `newborn`, so every
+/// fish is in one of `newborn + 1` timer buckets.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Lifecycle {
+    reset: usize,
+    newborn: usize
+}
+
+impl Default for Lifecycle {
+    /// The puzzle's fish: a 7 day cycle and 2 more days for newborns.
+    fn default() -> Self {
+        Lifecycle { reset: 6, newborn: 8 }
+    }
+}
+
+impl Lifecycle {
+    /// `None` if a reset fish would land past the newborns' bucket.
+    pub fn new(reset: usize, newborn: usize) -> Option<Self> {
+        (reset <= newborn).then_some(Lifecycle { reset, newborn })
+    }
+
+    pub fn buckets(&self) -> usize {
+        self.newborn + 1
+    }
+
+    /// How many fish have each timer.
+    pub fn histogram<R: Ring>(&self, timers: &[usize], ring: &R) -> Result<Vec<R::Value>, String> {
+        let mut counts = vec![ring.value(0); self.buckets()];
+        for timer in timers {
+            let count = counts.get_mut(*timer)
+                .ok_or_else(|| format!("timer {} is past the newborn timer {}", timer, self.newborn))?;
+            *count = ring.add(count, &ring.value(1));
+        }
+        Ok(counts)
+    }
+
+    pub fn step<R: Ring>(&self, counts: &[R::Value], ring: &R) -> Vec<R::Value> {
+        let mut next = counts[1..].to_vec();
+        next.push(counts[0].clone());
+        next[self.reset] = ring.add(&next[self.reset], &counts[0]);
+        next
+    }
+
+    /// The histogram on each day from today to `days` days on, inclusive.
+    pub fn series<R: Ring>(&self, counts: &[R::Value], days: usize, ring: &R) -> Vec<Vec<R::Value>> {
+        let mut series = vec![counts.to_vec()];
+        for _ in 0..days {
+            let next = self.step(&series[series.len() - 1], ring);
+            series.push(next);
+        }
+        series
+    }
+
+    /// The histogram `days` days on, found by raising the one-day
+    /// transition matrix to that power rather than stepping through.
+    pub fn jump<R: Ring>(&self, counts: &[R::Value], days: u64, ring: &R) -> Vec<R::Value> {
+        let mut transitions = Matrix::zero(self.buckets(), ring);
+        for timer in 1..self.buckets() {
+            transitions.add_to(timer - 1, timer, 1, ring);
+        }
+        transitions.add_to(self.reset, 0, 1, ring);
+        transitions.add_to(self.newborn, 0, 1, ring);
+
+        transitions.pow_apply(days, counts.to_vec(), ring)
+    }
+}
+
+/// One row per day: the day, the count in each timer bucket, then the total.
+pub fn to_csv<R: Ring>(series: &[Vec<R::Value>], ring: &R) -> String where R::Value: Display {
+    let buckets = series.first().map_or(0, |counts| counts.len());
+    let mut header = vec!["day".to_string()];
+    header.extend((0..buckets).map(|timer| timer.to_string()));
+    header.push("total".to_string());
+
+    let mut lines = vec![header.join(",")];
+    for (day, counts) in series.iter().enumerate() {
+        let mut row = vec![day.to_string()];
+        row.extend(counts.iter().map(|count| count.to_string()));
+        row.push(ring.sum(counts).to_string());
+        lines.push(row.join(","));
+    }
+    lines.join("\n") + "\n"
+}
+
+#[cfg(test)]
+mod test {
+    use aoc_utils::bigint::BigUint;
+    use aoc_utils::matrix::{Exact, Modulo};
+    use super::*;
+
+    fn example<R: Ring>(lifecycle: &Lifecycle, ring: &R) -> Vec<R::Value> {
+        lifecycle.histogram(&[3, 4, 3, 1, 2], ring).unwrap()
+    }
+
+    /// Follows every fish one by one.
+    fn simulate(lifecycle: &Lifecycle, timers: &[usize], days: usize) -> usize {
+        let mut fish = timers.to_vec();
+        for _ in 0..days {
+            let spawned = fish.iter().filter(|t| **t == 0).count();
+            for timer in fish.iter_mut() {
+                *timer = if *timer == 0 { lifecycle.reset } else { *timer - 1 };
+            }
+            fish.extend(vec![lifecycle.newborn; spawned]);
+        }
+        fish.len()
+    }
+
+    #[test]
+    fn test_step() {
+        let lifecycle = Lifecycle::default();
+        let series = lifecycle.series(&example(&lifecycle, &Exact), 2, &Exact);
+
+        // Day 2 of the example is 1,2,1,6,0,8.
+        assert_eq!(lifecycle.histogram(&[1, 2, 1, 6, 0, 8], &Exact).unwrap(), series[2]);
+    }
+
+    #[test]
+    fn test_to_csv() {
+        let lifecycle = Lifecycle::default();
+        let ring = Modulo::new(1_000_000_007).unwrap();
+        let csv = to_csv(&lifecycle.series(&example(&lifecycle, &ring), 18, &ring), &ring);
+        let lines = csv.lines().collect::<Vec<_>>();
+
+        assert_eq!("day,0,1,2,3,4,5,6,7,8,total", lines[0]);
+        assert_eq!("0,0,1,1,2,1,0,0,0,0,5", lines[1]);
+        assert_eq!(20, lines.len());
+        assert!(lines[19].ends_with(",26"));
+    }
+
+    #[test]
+    fn test_jump_matches_series() {
+        let lifecycle = Lifecycle::default();
+        let counts = example(&lifecycle, &Exact);
+        let series = lifecycle.series(&counts, 300, &Exact);
+        for days in [0, 1, 18, 80, 256, 300] {
+            assert_eq!(series[days], lifecycle.jump(&counts, days as u64, &Exact));
+        }
+        assert_eq!(BigUint::from(26984457539), Exact.sum(&lifecycle.jump(&counts, 256, &Exact)));
+    }
+
+    #[test]
+    fn test_jump_modulo_a_prime() {
+        let lifecycle = Lifecycle::default();
+        let p = 1_000_000_007;
+        let ring = Modulo::new(p).unwrap();
+
+        let exact = Exact.sum(&lifecycle.jump(&example(&lifecycle, &Exact), 5000, &Exact));
+        let modulo = ring.sum(&lifecycle.jump(&example(&lifecycle, &ring), 5000, &ring));
+        assert_eq!(exact.rem_u64(p), modulo);
+
+        // Far enough that stepping day by day is out of the question.
+        let far = lifecycle.jump(&example(&lifecycle, &ring), 1_000_000_000_000_000_000, &ring);
+        assert_eq!(9, far.len());
+    }
+
+    #[test]
+    fn test_other_lifecycles() {
+        for (reset, newborn) in [(6, 8), (2, 3), (0, 0), (4, 4)] {
+            let lifecycle = Lifecycle::new(reset, newborn).unwrap();
+            let timers = [0, newborn, reset];
+            let counts = lifecycle.histogram(&timers, &Exact).unwrap();
+            for days in [0, 5, 20] {
+                let expected = BigUint::from(simulate(&lifecycle, &timers, days) as u64);
+                assert_eq!(expected, Exact.sum(&lifecycle.jump(&counts, days as u64, &Exact)));
+            }
+        }
+        assert_eq!(None, Lifecycle::new(9, 8));
+    }
+
+    #[test]
+    fn test_histogram_rejects_large_timers() {
+        let error = Lifecycle::default().histogram(&[1, 9], &Exact).unwrap_err();
+        assert_eq!("timer 9 is past the newborn timer 8", error);
+    }
+}
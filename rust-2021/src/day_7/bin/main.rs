@@ -1,13 +1,37 @@
-use std::cmp::min;
+use std::env;
 use std::error::Error;
 use aoc_utils::read_file;
+use crate::optimise::{Cost, OptimiseError, Strategy, optimise};
+
+mod optimise;
 
 fn main() -> Result<(), Box<dyn Error>> {
     let contents = read_file("./rust-2021/inputs/day_7_input")?;
     let parsed_input = parse(contents)?;
 
-    println!("{:?}", calculate_part_1(&parsed_input));
-    println!("{:?}", calculate_part_2(&parsed_input));
+    let args = env::args().collect::<Vec<_>>();
+    match args.get(1) {
+        Some(name) => {
+            let cost = match name.as_str() {
+                "linear" => Cost::Linear,
+                "triangular" => Cost::Triangular,
+                "quadratic" => Cost::Quadratic,
+                other => {
+                    let k = other.strip_prefix("power=")
+                        .ok_or("usage: day_7 [linear|triangular|quadratic|power=K] [--brute]")?
+                        .parse::<u32>()?;
+                    Cost::Custom(Box::new(move |d: i64| d.checked_pow(k)))
+                }
+            };
+            let strategy = if args.iter().any(|a| a == "--brute") { Strategy::BruteForce } else { Strategy::Search };
+            let (position, fuel) = optimise(&parsed_input, &cost, strategy)?;
+            println!("position {} costs {}", position, fuel);
+        },
+        None => {
+            println!("{:?}", calculate_part_1(&parsed_input)?);
+            println!("{:?}", calculate_part_2(&parsed_input)?);
+        }
+    }
 
     Ok(())
 }
@@ -17,43 +41,20 @@ type Output = i64;
 
 fn parse(contents: Vec<String>) -> Result<Input, Box<dyn Error>> {
     Ok(contents[0].split(',')
-        .map(|e| e.parse::<i64>().unwrap())
-        .collect())
+        .map(|e| e.parse::<i64>())
+        .collect::<Result<_, _>>()?)
 }
 
-fn calculate_part_1(input: &Input) -> Output {
-    let mut spots = input.clone();
-    spots.sort();
-
-    let median = if spots.len() % 2 == 0 {
-        spots[spots.len() / 2]
-    } else {
-        (spots[spots.len() / 2] + spots[spots.len() / 2 - 1]) / 2
-    };
-
-    spots.iter()
-        .map(|i| (i - median).abs())
-        .sum()
+fn cheapest(input: &Input, cost: Cost) -> Result<Output, OptimiseError> {
+    optimise(input, &cost, Strategy::Search).map(|(_, fuel)| fuel)
 }
 
-fn calc(mean: i64, input: &Input) -> Output {
-    input.iter()
-        .map(|i| (mean - i).abs())
-        .map(|i| (i * (i + 1)) / 2)
-        .sum()
+fn calculate_part_1(input: &Input) -> Result<Output, OptimiseError> {
+    cheapest(input, Cost::Linear)
 }
 
-fn calculate_part_2(input: &Input) -> Output {
-    let total = input.iter().sum::<i64>();
-    let mean = total as f64 / (input.len() as f64);
-
-    let mean_ceil = mean.ceil() as i64;
-    let ceil_mean = calc(mean_ceil, &input);
-
-    let mean_floor = mean.floor() as i64;
-    let floor_mean = calc(mean_floor, &input);
-
-    min(ceil_mean, floor_mean)
+fn calculate_part_2(input: &Input) -> Result<Output, OptimiseError> {
+    cheapest(input, Cost::Triangular)
 }
 
 #[cfg(test)]
@@ -68,7 +69,7 @@ mod test {
         let contents = read_file("./inputs/day_7_test_input")?;
         let input = parse(contents)?;
 
-        assert_eq!(37, calculate_part_1(&input));
+        assert_eq!(37, calculate_part_1(&input)?);
         Ok(())
     }
 
@@ -77,7 +78,15 @@ mod test {
         let contents = read_file("./inputs/day_7_test_input")?;
         let input = parse(contents)?;
 
-        assert_eq!(168, calculate_part_2(&input));
+        assert_eq!(168, calculate_part_2(&input)?);
         Ok(())
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_overflow_is_an_error() {
+        // every position is at least 2^32 from some crab
+        let input = vec![0, 8_589_934_592, 8_589_934_592];
+        assert_eq!(Ok(8_589_934_592), calculate_part_1(&input));
+        assert!(matches!(calculate_part_2(&input), Err(OptimiseError::Overflow { .. })));
+    }
+}
@@ -0,0 +1,166 @@
+use std::error::Error;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+/// The fuel one crab burns to move a given distance. Any cost works as long
+/// as it never decreases and is convex in the distance, which makes the
+/// total over all crabs convex in the target position.
+pub enum Cost {
+    Linear,
+    Triangular,
+    Quadratic,
+    /// Returns `None` when the cost doesn't fit in an `i64`.
+    Custom(Box<dyn Fn(i64) -> Option<i64>>)
+}
+
+impl Cost {
+    /// `None` when the cost doesn't fit in an `i64`.
+    pub fn of(&self, distance: i64) -> Option<i64> {
+        match self {
+            Cost::Linear => Some(distance),
+            // halve whichever factor is even first, so only the result has
+            // to fit
+            Cost::Triangular if distance % 2 == 0 => (distance / 2).checked_mul(distance + 1),
+            Cost::Triangular => distance.checked_mul((distance + 1) / 2),
+            Cost::Quadratic => distance.checked_mul(distance),
+            Cost::Custom(f) => f(distance)
+        }
+    }
+
+    /// `None` when a crab's cost or the sum doesn't fit in an `i64`.
+    pub fn total(&self, crabs: &[i64], position: i64) -> Option<i64> {
+        crabs.iter().try_fold(0i64, |total, crab| total.checked_add(self.of(crab.checked_sub(position)?.checked_abs()?)?))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Strategy {
+    /// Binary search on the sign of the total's slope.
+    Search,
+    /// Tries every position; only there to check `Search` against.
+    BruteForce
+}
+
+#[derive(Debug, PartialEq)]
+pub enum OptimiseError {
+    NoCrabs,
+    /// The total cost of moving every crab to `position` doesn't fit in an
+    /// `i64`.
+    Overflow { position: i64 }
+}
+
+impl Display for OptimiseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            OptimiseError::NoCrabs => write!(f, "there are no crabs"),
+            OptimiseError::Overflow { position } => write!(f, "the fuel to reach position {} overflows an i64", position)
+        }
+    }
+}
+
+impl Error for OptimiseError {}
+
+/// The leftmost cheapest position between the outermost crabs, with its
+/// total cost. Fails if any total it has to compare overflows.
+pub fn optimise(crabs: &[i64], cost: &Cost, strategy: Strategy) -> Result<(i64, i64), OptimiseError> {
+    let lo = *crabs.iter().min().ok_or(OptimiseError::NoCrabs)?;
+    let hi = *crabs.iter().max().ok_or(OptimiseError::NoCrabs)?;
+    let total = |position| cost.total(crabs, position).ok_or(OptimiseError::Overflow { position });
+
+    match strategy {
+        Strategy::Search => {
+            // the first position where moving right stops paying off
+            let (mut lo, mut hi) = (lo, hi);
+            while lo < hi {
+                let mid = lo + (hi - lo) / 2;
+                if total(mid + 1)? >= total(mid)? {
+                    hi = mid;
+                } else {
+                    lo = mid + 1;
+                }
+            }
+            Ok((lo, total(lo)?))
+        },
+        Strategy::BruteForce => {
+            let mut best = (lo, total(lo)?);
+            for position in lo + 1..=hi {
+                let fuel = total(position)?;
+                if fuel < best.1 {
+                    best = (position, fuel);
+                }
+            }
+            Ok(best)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use aoc_utils::rng::Rng;
+    use super::*;
+
+    fn costs() -> Vec<Cost> {
+        vec![
+            Cost::Linear,
+            Cost::Triangular,
+            Cost::Quadratic,
+            Cost::Custom(Box::new(|d| d.checked_pow(3)?.checked_add(5 * d))),
+            Cost::Custom(Box::new(|d| Some((d - 3).max(0))))
+        ]
+    }
+
+    const EXAMPLE: [i64; 10] = [16, 1, 2, 0, 4, 2, 7, 1, 2, 14];
+
+    #[test]
+    fn test_example() {
+        assert_eq!(Ok((2, 37)), optimise(&EXAMPLE, &Cost::Linear, Strategy::Search));
+        assert_eq!(Ok((5, 168)), optimise(&EXAMPLE, &Cost::Triangular, Strategy::Search));
+        assert_eq!(Ok((5, 291)), optimise(&EXAMPLE, &Cost::Quadratic, Strategy::Search));
+    }
+
+    #[test]
+    fn test_no_crabs() {
+        assert_eq!(Err(OptimiseError::NoCrabs), optimise(&[], &Cost::Linear, Strategy::Search));
+    }
+
+    #[test]
+    fn test_even_count_median() {
+        // Any position from 0 to 2 costs 12; the leftmost wins.
+        assert_eq!(Ok((0, 12)), optimise(&[0, 2, 10, 0], &Cost::Linear, Strategy::Search));
+        assert_eq!(Ok((0, 12)), optimise(&[0, 2, 10, 0], &Cost::Linear, Strategy::BruteForce));
+    }
+
+    #[test]
+    fn test_search_matches_brute_force() {
+        let mut rng = Rng::new(0x2021_0007);
+        for _ in 0..200 {
+            let len = 1 + rng.below(30);
+            let spread = 1 + rng.below(500);
+            let crabs = (0..len).map(|_| rng.below(spread) as i64 - 100).collect::<Vec<_>>();
+
+            for cost in costs() {
+                assert_eq!(optimise(&crabs, &cost, Strategy::BruteForce), optimise(&crabs, &cost, Strategy::Search),
+                           "crabs {:?}", crabs);
+            }
+        }
+    }
+
+    #[test]
+    fn test_costs_near_the_limit() {
+        // 4294967295 * 4294967296 / 2 fits even though the product doesn't
+        assert_eq!(Some(9_223_372_034_707_292_160), Cost::Triangular.of(4_294_967_295));
+        assert_eq!(None, Cost::Triangular.of(4_294_967_296));
+        assert_eq!(Some(9_223_372_030_926_249_001), Cost::Quadratic.of(3_037_000_499));
+        assert_eq!(None, Cost::Quadratic.of(3_037_000_500));
+        assert_eq!(None, Cost::Linear.total(&[i64::MAX, i64::MAX], 0));
+        assert_eq!(None, Cost::Linear.total(&[i64::MIN], 1));
+    }
+
+    #[test]
+    fn test_overflow() {
+        // every position is at least 5e9 from one of the crabs
+        let crabs = [0, 10_000_000_000];
+        assert!(matches!(optimise(&crabs, &Cost::Quadratic, Strategy::Search), Err(OptimiseError::Overflow { .. })));
+        assert_eq!(Ok((0, 10_000_000_000)), optimise(&crabs, &Cost::Linear, Strategy::Search));
+    }
+}
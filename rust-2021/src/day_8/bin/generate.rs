@@ -0,0 +1,108 @@
+use aoc_utils::rng::Rng;
+use crate::solver::{Alphabet, Mask};
+
+/// One input line and what its output should decode to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Scrambled {
+    pub line: String,
+    pub expected: String
+}
+
+/// Rewires the display at random, then writes every glyph in a random order
+/// followed by `outputs` random glyphs, each with its wires shuffled too.
+pub fn scramble(alphabet: &Alphabet, outputs: usize, rng: &mut Rng) -> Scrambled {
+    let mut wire_of = (0..alphabet.segments()).collect::<Vec<_>>();
+    rng.shuffle(&mut wire_of);
+
+    let render = |segments: Mask, rng: &mut Rng| {
+        let mut letters = wire_of.iter().enumerate()
+            .filter(|(segment, _)| segments >> segment & 1 == 1)
+            .map(|(_, wire)| (b'a' + *wire as u8) as char)
+            .collect::<Vec<_>>();
+        rng.shuffle(&mut letters);
+        letters.into_iter().collect::<String>()
+    };
+
+    let glyphs = alphabet.glyphs();
+    let mut patterns = glyphs.iter().map(|(_, mask)| render(*mask, rng)).collect::<Vec<_>>();
+    let chosen = (0..outputs).map(|_| glyphs[rng.below(glyphs.len())]).collect::<Vec<_>>();
+    let output = chosen.iter().map(|(_, mask)| render(*mask, rng)).collect::<Vec<_>>();
+    rng.shuffle(&mut patterns);
+
+    Scrambled {
+        line: format!("{} | {}", patterns.join(" "), output.join(" ")),
+        expected: chosen.iter().map(|(symbol, _)| *symbol).collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use aoc_utils::Puzzle;
+    use crate::{Day8, parse_segments};
+    use crate::solver::SolveError;
+    use super::*;
+
+    #[test]
+    fn test_format() {
+        let scrambled = scramble(&Alphabet::seven_segment(), 4, &mut Rng::new(8));
+        let (patterns, output) = scrambled.line.split_once(" | ").unwrap();
+
+        let mut lengths = patterns.split(' ').map(|p| p.len()).collect::<Vec<_>>();
+        lengths.sort();
+        assert_eq!(vec![2, 3, 4, 5, 5, 5, 6, 6, 6, 7], lengths);
+        assert_eq!(4, output.split(' ').count());
+        assert_eq!(4, scrambled.expected.len());
+    }
+
+    #[test]
+    fn test_same_seed_same_line() {
+        let alphabet = Alphabet::seven_segment();
+        assert_eq!(scramble(&alphabet, 4, &mut Rng::new(1)), scramble(&alphabet, 4, &mut Rng::new(1)));
+        assert_ne!(scramble(&alphabet, 4, &mut Rng::new(1)), scramble(&alphabet, 4, &mut Rng::new(2)));
+    }
+
+    #[test]
+    fn test_fuzz_solve() {
+        let day = Day8(Alphabet::seven_segment());
+        let mut rng = Rng::new(2021);
+        let scrambled = (0..3000).map(|_| scramble(&day.0, 4, &mut rng)).collect::<Vec<_>>();
+
+        let input = day.parse(scrambled.iter().map(|s| s.line.clone()).collect()).unwrap();
+        let unsorted = input.iter()
+            .filter(|s| s.signal_patterns.windows(2).any(|w| w[0].count_ones() > w[1].count_ones()))
+            .count();
+        assert!(unsorted > 2900);
+
+        for (segments, scrambled) in input.iter().zip(&scrambled) {
+            assert_eq!(Ok(scrambled.expected.clone()), segments.solve(&day.0), "{}", scrambled.line);
+        }
+
+        let total = scrambled.iter().map(|s| s.expected.parse::<i32>().unwrap()).sum::<i32>();
+        assert_eq!(total, day.calculate_part_2(&input));
+    }
+
+    #[test]
+    fn test_fuzz_other_alphabet() {
+        let alphabet = Alphabet::new(5, &[
+            ('+', "bcd"), ('-', "c"), ('<', "ab"), ('>', "de"), ('=', "ad"), ('#', "abcde")
+        ]).unwrap();
+        let mut rng = Rng::new(5);
+        for _ in 0..500 {
+            let scrambled = scramble(&alphabet, 6, &mut rng);
+            let input = Day8(alphabet.clone()).parse(vec![scrambled.line.clone()]).unwrap();
+            assert_eq!(Ok(scrambled.expected), input[0].solve(&alphabet), "{}", scrambled.line);
+        }
+    }
+
+    #[test]
+    fn test_symmetric_alphabet_is_ambiguous() {
+        // Swapping a with e and b with d maps every glyph onto another.
+        let alphabet = Alphabet::new(5, &[
+            ('+', "bcd"), ('-', "c"), ('<', "ab"), ('>', "de"), ('=', "ae"), ('#', "abcde")
+        ]).unwrap();
+        let scrambled = scramble(&alphabet, 1, &mut Rng::new(5));
+        let input = parse_segments(vec![scrambled.line.clone()]).unwrap();
+        assert!(matches!(input[0].solve(&alphabet), Err(SolveError::Ambiguous(_, _))));
+        assert!(Day8(alphabet).parse(vec![scrambled.line]).unwrap_err().to_string().starts_with("line 1: the patterns are ambiguous"));
+    }
+}
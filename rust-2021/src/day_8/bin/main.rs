@@ -1,11 +1,63 @@
-use std::collections::{BTreeSet, HashMap};
+use std::env;
 use std::error::Error;
-use aoc_utils::{Puzzle, run_all};
+use aoc_utils::{Puzzle, read_file, run_all};
+use aoc_utils::rng::Rng;
+use crate::generate::scramble;
+use crate::solver::{Alphabet, MAX_SEGMENTS, Mask, SolveError, letter_mask};
 
-struct Day8;
+mod generate;
+mod solver;
+
+struct Day8(Alphabet);
 
 fn main() -> Result<(), Box<dyn Error>> {
-    run_all("./rust-2021/inputs/day_8_input", Box::new(Day8 {}))
+    let args = env::args().collect::<Vec<_>>();
+    match args.get(1).map(|s| s.as_str()) {
+        Some("decode") => {
+            let usage = "usage: day_8 decode <alphabet file> <input file>";
+            let alphabet = parse_alphabet(read_file(args.get(2).ok_or(usage)?)?)?;
+            let puzzle = Day8(alphabet);
+            for (i, segments) in parse_segments(read_file(args.get(3).ok_or(usage)?)?)?.iter().enumerate() {
+                match segments.solve(&puzzle.0) {
+                    Ok(decoded) => println!("{}", decoded),
+                    Err(e) => println!("line {}: {}", i + 1, e)
+                }
+            }
+            Ok(())
+        },
+        Some("generate") => {
+            let count = args.get(2).ok_or("usage: day_8 generate <lines> [seed]")?.parse::<usize>()?;
+            let seed = args.get(3).map_or(Ok(2021), |s| s.parse())?;
+            let mut rng = Rng::new(seed);
+            // the lines are valid input; the answers go to stderr to keep them apart
+            for _ in 0..count {
+                let scrambled = scramble(&Alphabet::seven_segment(), 4, &mut rng);
+                println!("{}", scrambled.line);
+                eprintln!("{}", scrambled.expected);
+            }
+            Ok(())
+        },
+        _ => run_all("./rust-2021/inputs/day_8_input", Box::new(Day8(Alphabet::seven_segment())))
+    }
+}
+
+/// The number of segments on the first line, then a symbol and the
+/// segments it lights on each line after.
+fn parse_alphabet(contents: Vec<String>) -> Result<Alphabet, Box<dyn Error>> {
+    let segments = contents.first().ok_or("the alphabet is empty")?.trim().parse()?;
+    let glyphs = contents[1..].iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            let (symbol, letters) = line.trim().split_once(' ').ok_or(format!("expected a symbol and segments in {:?}", line))?;
+            let mut chars = symbol.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => Ok((c, letters.trim())),
+                _ => Err(format!("{:?} isn't a single symbol", symbol))
+            }
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(Alphabet::new(segments, &glyphs)?)
 }
 
 type Input = Vec<Segments>;
@@ -13,135 +65,71 @@ type Output = i32;
 
 #[derive(Debug)]
 struct Segments {
-    signal_patterns: Vec<BTreeSet<u8>>,
-    digit_output: Vec<BTreeSet<u8>>
+    signal_patterns: Vec<Mask>,
+    digit_output: Vec<Mask>
 }
 
-fn parse_patterns(line: &String) -> Vec<BTreeSet<u8>> {
+fn parse_patterns(line: &str) -> Result<Vec<Mask>, String> {
     line.split(' ')
         .filter(|s| !s.is_empty())
-        .map(|s| s.bytes().collect::<BTreeSet<_>>())
-        .collect::<Vec<BTreeSet<_>>>()
+        .map(|s| letter_mask(s, MAX_SEGMENTS))
+        .collect()
 }
-/*
-  0:      1:      2:      3:      4:
- aaaa    ....    aaaa    aaaa    ....
-b    c  .    c  .    c  .    c  b    c
-b    c  .    c  .    c  .    c  b    c
- ....    ....    dddd    dddd    dddd
-e    f  .    f  e    .  .    f  .    f
-e    f  .    f  e    .  .    f  .    f
- gggg    ....    gggg    gggg    ....
-
-  5:      6:      7:      8:      9:
- aaaa    aaaa    aaaa    aaaa    aaaa
-b    .  b    .  .    c  b    c  b    c
-b    .  b    .  .    c  b    c  b    c
- dddd    dddd    ....    dddd    dddd
-.    f  e    f  .    f  e    f  .    f
-.    f  e    f  .    f  e    f  .    f
- gggg    gggg    ....    gggg    gggg
-
-
-1 has 2 chars
-7 has 3 chars
-4 has 4 chars
-8 has 7 chars
-
-5 chars => 2, 3, 5
-6 chars => 0, 6, 9
-
-7 is found in 3
-2 contains only 2 parts of 4
-5 is the other one
-
-9 contains all of 4
-6 contains all of 5
-0 is the other one
- */
+
 impl Segments {
+    fn solve(&self, alphabet: &Alphabet) -> Result<String, SolveError> {
+        alphabet.decode(&self.signal_patterns, &self.digit_output)
+    }
+}
 
-    fn find_mapping(&self) -> HashMap<&BTreeSet<u8>, String> {
-        let mut digits_mapping = HashMap::<i32, &BTreeSet<_>>::new();
-        for signal in &self.signal_patterns {
-            match signal.len() {
-                2 => { digits_mapping.insert(1, signal); },
-                3 => { digits_mapping.insert(7, signal); },
-                4 => { digits_mapping.insert(4, signal); },
-                5 => {
-                    if digits_mapping[&7].is_subset(signal) {
-                        digits_mapping.insert(3, signal);
-                    } else if digits_mapping[&4].intersection(signal).cloned().collect::<Vec<u8>>().len() == 2 {
-                        digits_mapping.insert(2, signal);
-                    } else {
-                        digits_mapping.insert(5, signal);
-                    }
-                },
-                6 => {
-                    if digits_mapping[&4].is_subset(signal) {
-                        digits_mapping.insert(9, signal);
-                    } else if digits_mapping[&5].is_subset(signal) {
-                        digits_mapping.insert(6, signal);
-                    } else {
-                        digits_mapping.insert(0, signal);
-                    }
-                },
-                _ => { digits_mapping.insert(8, signal); }
-            }
-        }
+/// Reads each line's patterns and output without solving them.
+fn parse_segments(contents: Vec<String>) -> Result<Input, Box<dyn Error>> {
+    let mut results = vec![];
+    for line in contents {
+        let (patterns, output) = line.split_once('|')
+            .ok_or(format!("expected patterns | output in {:?}", line))?;
 
-        let mut swapped = HashMap::new();
-        for (key, value) in digits_mapping {
-            swapped.insert(value, format!("{}", key));
-        }
+        let signal_patterns = parse_patterns(patterns)?;
+        let digit_output = parse_patterns(output)?;
 
-        swapped
+        results.push(Segments { signal_patterns, digit_output })
     }
 
-    fn solve(&self) -> i32 {
-        let mapping = self.find_mapping();
-        let digits = self.digit_output.iter()
-            .map(|digit| mapping[digit].clone())
-            .collect::<String>();
-
-        digits.parse::<i32>().unwrap()
-    }
+    Ok(results)
 }
 
 impl Puzzle<Input, Output> for Day8 {
+    /// Rejects any line the alphabet can't decode, so part two doesn't
+    /// have to.
     fn parse(&self, contents: Vec<String>) -> Result<Input, Box<dyn Error>> {
-        let mut results = vec![];
-        for line in contents {
-            let line_split = line.split('|')
-                .map(String::from)
-                .collect::<Vec<String>>();
-
-            let mut signal_patterns = parse_patterns(&line_split[0]);
-            signal_patterns.sort_by(|a, b| a.len().cmp(&b.len()));
-
-            let digit_output = parse_patterns(&line_split[1]);
-
-            results.push(Segments { signal_patterns, digit_output })
+        let input = parse_segments(contents)?;
+        for (i, segments) in input.iter().enumerate() {
+            segments.solve(&self.0).map_err(|e| format!("line {}: {}", i + 1, e))?;
         }
 
-        Ok(results)
+        Ok(input)
     }
 
+    /// Counts the outputs that can only be one symbol, going by how many
+    /// segments are lit.
     fn calculate_part_1(&self, input: &Input) -> Output {
-        let mut count = 0;
-        for segment in input {
-            for digit in &segment.digit_output {
-                if digit.len() == 2 || digit.len() == 3 || digit.len() == 4 || digit.len() == 7 {
-                    count += 1;
-                }
-            }
-        }
-        count
+        let glyphs = self.0.glyphs();
+        let unique = |wires: &Mask| glyphs.iter()
+            .filter(|(_, g)| g.count_ones() == wires.count_ones())
+            .count() == 1;
+
+        input.iter()
+            .flat_map(|segment| &segment.digit_output)
+            .filter(|digit| unique(digit))
+            .count() as Output
     }
 
     fn calculate_part_2(&self, input: &Input) -> Output {
         input.iter()
-            .map(|segment| segment.solve() )
+            .map(|segment| segment.solve(&self.0)
+                .expect("parse checks every line decodes")
+                .parse::<Output>()
+                .expect("the output is made of digits"))
             .sum()
     }
 }
@@ -153,15 +141,46 @@ mod test {
 
     #[test]
     fn test_part_one() -> Result<(), Box<dyn Error>> {
-        assert_eq!(26, run_part_one("./inputs/day_8_test_input", Box::new(Day8 {}))?);
+        assert_eq!(26, run_part_one("./inputs/day_8_test_input", Box::new(Day8(Alphabet::seven_segment())))?);
 
         Ok(())
     }
 
     #[test]
     fn test_part_two() -> Result<(), Box<dyn Error>> {
-        assert_eq!(61229, run_part_two("./inputs/day_8_test_input", Box::new(Day8 {}))?);
+        assert_eq!(61229, run_part_two("./inputs/day_8_test_input", Box::new(Day8(Alphabet::seven_segment())))?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let day = Day8(Alphabet::seven_segment());
+        assert!(day.parse(vec!["ab cd".to_string()]).is_err());
+        assert!(day.parse(vec!["ab c! | ab".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_parse_rejects_lines_that_dont_decode() -> Result<(), Box<dyn Error>> {
+        let day = Day8(Alphabet::seven_segment());
+        let mut lines = read_file("./inputs/day_8_test_input")?;
+        lines[3] = "a | ab".to_string();
+        assert_eq!("line 4: no wiring fits the patterns", day.parse(lines.clone()).unwrap_err().to_string());
+
+        let (patterns, _) = lines[0].split_once(" | ").unwrap();
+        lines[3] = format!("{} | {}", patterns, "abcdefg a");
+        assert_eq!("line 4: output 2 isn't any glyph under the wiring", day.parse(lines.clone()).unwrap_err().to_string());
+        assert_eq!(10, parse_segments(lines)?.len());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_alphabet() -> Result<(), Box<dyn Error>> {
+        let lines = ["3", "x a", "y ab", "", "z abc"].iter().map(|s| s.to_string()).collect();
+        assert_eq!(Alphabet::new(3, &[('x', "a"), ('y', "ab"), ('z', "abc")])?, parse_alphabet(lines)?);
+        assert!(parse_alphabet(vec!["3".to_string(), "xy a".to_string()]).is_err());
 
         Ok(())
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,316 @@
+use std::error::Error;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+/// A set of segments, or of wires, with bit `i` for letter `'a' + i`. Sixteen
+/// bits leaves room for fourteen and sixteen segment displays.
+pub type Mask = u16;
+
+pub const MAX_SEGMENTS: usize = Mask::BITS as usize;
+
+pub fn letter_mask(letters: &str, segments: usize) -> Result<Mask, String> {
+    letters.bytes().try_fold(0, |mask, b| {
+        let i = b.wrapping_sub(b'a') as usize;
+        if i >= segments {
+            return Err(format!("{:?} isn't one of the {} segment letters", b as char, segments));
+        }
+        Ok(mask | 1 << i)
+    })
+}
+
+/// Which segments light up for each symbol a display can show.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Alphabet {
+    segments: usize,
+    glyphs: Vec<(char, Mask)>
+}
+
+impl Alphabet {
+    /// `glyphs` pairs each symbol with its lit segments, written as letters
+    /// from `'a'`.
+    pub fn new(segments: usize, glyphs: &[(char, &str)]) -> Result<Self, String> {
+        if segments > MAX_SEGMENTS {
+            return Err(format!("at most {} segments are supported", MAX_SEGMENTS));
+        }
+
+        let mut masks: Vec<(char, Mask)> = vec![];
+        for (symbol, letters) in glyphs {
+            let mask = letter_mask(letters, segments)?;
+            if let Some((other, _)) = masks.iter().find(|(_, m)| *m == mask) {
+                return Err(format!("{:?} and {:?} light the same segments", other, symbol));
+            }
+            masks.push((*symbol, mask));
+        }
+
+        Ok(Alphabet { segments, glyphs: masks })
+    }
+
+    /// The puzzle's digits, as drawn in its first example.
+    pub fn seven_segment() -> Self {
+        Alphabet::new(7, &[
+            ('0', "abcefg"), ('1', "cf"), ('2', "acdeg"), ('3', "acdfg"), ('4', "bcdf"),
+            ('5', "abdfg"), ('6', "abdefg"), ('7', "acf"), ('8', "abcdefg"), ('9', "abcdfg")
+        ]).expect("the standard digits are a valid alphabet")
+    }
+
+    pub fn segments(&self) -> usize {
+        self.segments
+    }
+
+    pub fn glyphs(&self) -> &[(char, Mask)] {
+        &self.glyphs
+    }
+
+    pub fn symbol(&self, segments: Mask) -> Option<char> {
+        self.glyphs.iter().find(|(_, mask)| *mask == segments).map(|(symbol, _)| *symbol)
+    }
+
+    /// The one wiring under which every observed wire pattern shows some
+    /// glyph.
+    pub fn solve(&self, observations: &[Mask]) -> Result<Wiring, SolveError> {
+        let full = if self.segments == MAX_SEGMENTS { Mask::MAX } else { (1 << self.segments) - 1 };
+        if observations.iter().any(|o| o & !full != 0) {
+            return Err(SolveError::NoSolution);
+        }
+
+        // The glyphs each observation could be, by number of lit segments.
+        let options = observations.iter()
+            .map(|o| self.glyphs.iter()
+                .map(|(_, g)| *g)
+                .filter(|g| g.count_ones() == o.count_ones())
+                .collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+
+        let mut candidates = vec![full; self.segments];
+        for (o, glyphs) in observations.iter().zip(&options) {
+            let lit = glyphs.iter().fold(0, |acc, g| acc | g);
+            let dark = glyphs.iter().fold(0, |acc, g| acc | (!g & full));
+            for (wire, segments) in candidates.iter_mut().enumerate() {
+                *segments &= if o >> wire & 1 == 1 { lit } else { dark };
+            }
+        }
+        settle(&mut candidates);
+
+        let mut search = Search { candidates, options: &options, observations, segment_of: vec![None; self.segments], found: vec![] };
+        search.run(0);
+
+        let mut found = search.found.into_iter();
+        match (found.next(), found.next()) {
+            (None, _) => Err(SolveError::NoSolution),
+            (Some(wiring), None) => Ok(wiring),
+            (Some(first), Some(second)) => Err(SolveError::Ambiguous(first, second))
+        }
+    }
+
+    /// Solves the patterns, then reads the output through the wiring.
+    pub fn decode(&self, patterns: &[Mask], output: &[Mask]) -> Result<String, SolveError> {
+        let wiring = self.solve(patterns)?;
+        output.iter()
+            .enumerate()
+            .map(|(i, wires)| self.symbol(wiring.translate(*wires)).ok_or(SolveError::NotAGlyph { position: i + 1 }))
+            .collect()
+    }
+}
+
+/// Removes a wire's segment from every other wire once it's the only one
+/// left, until nothing changes.
+fn settle(candidates: &mut [Mask]) {
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for wire in 0..candidates.len() {
+            let segment = candidates[wire];
+            if segment.count_ones() != 1 {
+                continue;
+            }
+            for (other, segments) in candidates.iter_mut().enumerate() {
+                if other != wire && *segments & segment != 0 {
+                    *segments &= !segment;
+                    changed = true;
+                }
+            }
+        }
+    }
+}
+
+struct Search<'a> {
+    candidates: Vec<Mask>,
+    options: &'a [Vec<Mask>],
+    observations: &'a [Mask],
+    segment_of: Vec<Option<usize>>,
+    found: Vec<Wiring>
+}
+
+impl Search<'_> {
+    /// Whether every observation can still be some glyph with the wires
+    /// placed so far.
+    fn consistent(&self) -> bool {
+        let image = |wires: Mask| self.segment_of.iter().enumerate()
+            .filter_map(|(wire, segment)| segment.filter(|_| wires >> wire & 1 == 1))
+            .fold(0 as Mask, |acc, segment| acc | 1 << segment);
+        let placed = image(Mask::MAX);
+
+        self.observations.iter().zip(self.options)
+            .all(|(o, glyphs)| {
+                let lit = image(*o);
+                glyphs.iter().any(|g| g & placed == lit)
+            })
+    }
+
+    /// Places the wire with the fewest segments left, stopping once a second
+    /// wiring turns up.
+    fn run(&mut self, used: Mask) {
+        let next = (0..self.segment_of.len())
+            .filter(|wire| self.segment_of[*wire].is_none())
+            .min_by_key(|wire| (self.candidates[*wire] & !used).count_ones());
+
+        let wire = match next {
+            Some(wire) => wire,
+            None => {
+                let segment_of = self.segment_of.iter().map(|s| s.expect("every wire is placed")).collect();
+                self.found.push(Wiring { segment_of });
+                return;
+            }
+        };
+
+        let options = self.candidates[wire] & !used;
+        for segment in (0..self.segment_of.len()).filter(|s| options >> s & 1 == 1) {
+            self.segment_of[wire] = Some(segment);
+            if self.consistent() {
+                self.run(used | 1 << segment);
+            }
+            self.segment_of[wire] = None;
+            if self.found.len() > 1 {
+                return;
+            }
+        }
+    }
+}
+
+/// Which segment each wire drives.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Wiring {
+    segment_of: Vec<usize>
+}
+
+impl Wiring {
+    pub fn translate(&self, wires: Mask) -> Mask {
+        self.segment_of.iter().enumerate()
+            .filter(|(wire, _)| wires >> wire & 1 == 1)
+            .fold(0, |acc, (_, segment)| acc | 1 << segment)
+    }
+}
+
+impl Display for Wiring {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let pairs = self.segment_of.iter().enumerate()
+            .map(|(wire, segment)| format!("{}->{}", (b'a' + wire as u8) as char, (b'a' + *segment as u8) as char))
+            .collect::<Vec<_>>();
+        write!(f, "{}", pairs.join(" "))
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum SolveError {
+    NoSolution,
+    /// Two of the wirings that fit; there may be more.
+    Ambiguous(Wiring, Wiring),
+    /// The patterns solve, but the output at `position`, counting from 1,
+    /// lights segments no glyph does.
+    NotAGlyph { position: usize }
+}
+
+impl Display for SolveError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            SolveError::NoSolution => write!(f, "no wiring fits the patterns"),
+            SolveError::Ambiguous(first, second) =>
+                write!(f, "the patterns are ambiguous: both {} and {} fit", first, second),
+            SolveError::NotAGlyph { position } => write!(f, "output {} isn't any glyph under the wiring", position)
+        }
+    }
+}
+
+impl Error for SolveError {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn masks(s: &str, segments: usize) -> Vec<Mask> {
+        s.split_whitespace().map(|p| letter_mask(p, segments).unwrap()).collect()
+    }
+
+    #[test]
+    fn test_example() {
+        let alphabet = Alphabet::seven_segment();
+        let patterns = masks("acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb ab", 7);
+        let output = masks("cdfeb fcadb cdfeb cdbaf", 7);
+
+        let wiring = alphabet.solve(&patterns).unwrap();
+        assert_eq!("a->c b->f c->g d->a e->b f->d g->e", wiring.to_string());
+        assert_eq!("5353", alphabet.decode(&patterns, &output).unwrap());
+    }
+
+    #[test]
+    fn test_output_not_a_glyph() {
+        let alphabet = Alphabet::seven_segment();
+        let patterns = masks("acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb ab", 7);
+        let error = alphabet.decode(&patterns, &masks("cdfeb fcadb a cdbaf", 7)).unwrap_err();
+        assert_eq!(SolveError::NotAGlyph { position: 3 }, error);
+        assert_eq!("output 3 isn't any glyph under the wiring", error.to_string());
+    }
+
+    #[test]
+    fn test_no_solution() {
+        let alphabet = Alphabet::seven_segment();
+        // Two different patterns with two wires, but only 1 lights two segments.
+        assert_eq!(Err(SolveError::NoSolution), alphabet.solve(&masks("ab cd", 7)));
+        // No digit lights a single segment.
+        assert_eq!(Err(SolveError::NoSolution), alphabet.solve(&masks("a", 7)));
+        // Wire h doesn't exist on a seven segment display.
+        assert_eq!(Err(SolveError::NoSolution), alphabet.solve(&[1 << 7]));
+    }
+
+    #[test]
+    fn test_ambiguous() {
+        let alphabet = Alphabet::seven_segment();
+        match alphabet.solve(&masks("ab", 7)) {
+            Err(SolveError::Ambiguous(first, second)) => {
+                assert_ne!(first, second);
+                assert_eq!(first.translate(0b11), second.translate(0b11));
+            },
+            other => panic!("expected an ambiguous wiring, got {:?}", other)
+        }
+    }
+
+    #[test]
+    fn test_custom_alphabet() {
+        let alphabet = Alphabet::new(3, &[('x', "a"), ('y', "ab"), ('z', "abc")]).unwrap();
+        let patterns = masks("c bc abc", 3);
+        assert_eq!("a->c b->b c->a", alphabet.solve(&patterns).unwrap().to_string());
+        assert_eq!("zyx", alphabet.decode(&patterns, &masks("cab cb c", 3)).unwrap());
+    }
+
+    #[test]
+    fn test_fourteen_segments() {
+        // Glyph i lights the first i + 1 segments, wired back to front.
+        let letters = (0..14).map(|i| (b'a'..=b'a' + i).map(|b| b as char).collect::<String>()).collect::<Vec<_>>();
+        let glyphs = letters.iter().enumerate()
+            .map(|(i, s)| ((b'A' + i as u8) as char, s.as_str()))
+            .collect::<Vec<_>>();
+        let alphabet = Alphabet::new(14, &glyphs).unwrap();
+
+        let patterns = letters.iter()
+            .map(|s| letter_mask(s, 14).unwrap().reverse_bits() >> 2)
+            .collect::<Vec<_>>();
+        assert_eq!("NAE", alphabet.decode(&patterns, &[patterns[13], patterns[0], patterns[4]]).unwrap());
+    }
+
+    #[test]
+    fn test_alphabet_errors() {
+        assert_eq!(Err("'h' isn't one of the 7 segment letters".to_string()), Alphabet::new(7, &[('x', "ah")]));
+        assert_eq!(Err("'x' and 'y' light the same segments".to_string()), Alphabet::new(7, &[('x', "ab"), ('y', "ba")]));
+        assert!(Alphabet::new(17, &[]).is_err());
+    }
+}
@@ -0,0 +1,252 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::fmt::{Debug, Display, Formatter};
+use std::iter::{Product, Sum};
+use std::ops::{Add, AddAssign, Mul};
+
+/// An arbitrary precision unsigned integer, stored as base 2^32 limbs with the
+/// least significant limb first and no trailing zero limbs.
+#[derive(Clone, Default, PartialEq, Eq, Hash)]
+pub struct BigUint {
+    limbs: Vec<u32>
+}
+
+impl BigUint {
+    pub fn zero() -> Self {
+        BigUint { limbs: vec![] }
+    }
+
+    pub fn one() -> Self {
+        BigUint::from(1)
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.limbs.is_empty()
+    }
+
+    pub fn to_u64(&self) -> Option<u64> {
+        match self.limbs.len() {
+            0 => Some(0),
+            1 => Some(self.limbs[0] as u64),
+            2 => Some(self.limbs[0] as u64 | (self.limbs[1] as u64) << 32),
+            _ => None
+        }
+    }
+
+    fn trim(mut self) -> Self {
+        while self.limbs.last() == Some(&0) {
+            self.limbs.pop();
+        }
+        self
+    }
+
+    /// The remainder after dividing by `modulus`, which mustn't be zero.
+    pub fn rem_u64(&self, modulus: u64) -> u64 {
+        self.limbs.iter().rev()
+            .fold(0u128, |rem, limb| ((rem << 32) | *limb as u128) % modulus as u128) as u64
+    }
+
+    /// Divides in place by a small divisor and returns the remainder.
+    fn div_rem_small(&mut self, divisor: u32) -> u32 {
+        let mut rem = 0u64;
+        for limb in self.limbs.iter_mut().rev() {
+            let cur = (rem << 32) | *limb as u64;
+            *limb = (cur / divisor as u64) as u32;
+            rem = cur % divisor as u64;
+        }
+        while self.limbs.last() == Some(&0) {
+            self.limbs.pop();
+        }
+        rem as u32
+    }
+}
+
+impl From<u64> for BigUint {
+    fn from(value: u64) -> Self {
+        BigUint { limbs: vec![value as u32, (value >> 32) as u32] }.trim()
+    }
+}
+
+impl Ord for BigUint {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.limbs.len().cmp(&other.limbs.len())
+            .then_with(|| self.limbs.iter().rev().cmp(other.limbs.iter().rev()))
+    }
+}
+
+impl PartialOrd for BigUint {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl AddAssign<&BigUint> for BigUint {
+    fn add_assign(&mut self, rhs: &BigUint) {
+        if self.limbs.len() < rhs.limbs.len() {
+            self.limbs.resize(rhs.limbs.len(), 0);
+        }
+
+        let mut carry = 0u64;
+        for (i, limb) in self.limbs.iter_mut().enumerate() {
+            let sum = *limb as u64 + *rhs.limbs.get(i).unwrap_or(&0) as u64 + carry;
+            *limb = sum as u32;
+            carry = sum >> 32;
+            if carry == 0 && i >= rhs.limbs.len() {
+                break;
+            }
+        }
+
+        if carry > 0 {
+            self.limbs.push(carry as u32);
+        }
+    }
+}
+
+impl Add<&BigUint> for &BigUint {
+    type Output = BigUint;
+
+    fn add(self, rhs: &BigUint) -> BigUint {
+        let mut sum = self.clone();
+        sum += rhs;
+        sum
+    }
+}
+
+impl Add for BigUint {
+    type Output = BigUint;
+
+    fn add(mut self, rhs: BigUint) -> BigUint {
+        self += &rhs;
+        self
+    }
+}
+
+impl Mul<&BigUint> for &BigUint {
+    type Output = BigUint;
+
+    fn mul(self, rhs: &BigUint) -> BigUint {
+        if self.is_zero() || rhs.is_zero() {
+            return BigUint::zero();
+        }
+
+        let mut limbs = vec![0u32; self.limbs.len() + rhs.limbs.len()];
+        for (i, &a) in self.limbs.iter().enumerate() {
+            let mut carry = 0u64;
+            for (j, &b) in rhs.limbs.iter().enumerate() {
+                let cur = limbs[i + j] as u64 + a as u64 * b as u64 + carry;
+                limbs[i + j] = cur as u32;
+                carry = cur >> 32;
+            }
+            limbs[i + rhs.limbs.len()] = carry as u32;
+        }
+
+        BigUint { limbs }.trim()
+    }
+}
+
+impl Mul for BigUint {
+    type Output = BigUint;
+
+    fn mul(self, rhs: BigUint) -> BigUint {
+        &self * &rhs
+    }
+}
+
+impl Sum for BigUint {
+    fn sum<I: Iterator<Item = BigUint>>(iter: I) -> Self {
+        iter.fold(BigUint::zero(), |acc, n| acc + n)
+    }
+}
+
+impl Product for BigUint {
+    fn product<I: Iterator<Item = BigUint>>(iter: I) -> Self {
+        iter.fold(BigUint::one(), |acc, n| acc * n)
+    }
+}
+
+impl Display for BigUint {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if self.is_zero() {
+            return write!(f, "0");
+        }
+
+        // peel off nine decimal digits at a time, least significant first
+        let mut rest = self.clone();
+        let mut chunks = vec![];
+        while !rest.is_zero() {
+            chunks.push(rest.div_rem_small(1_000_000_000));
+        }
+
+        write!(f, "{}", chunks[chunks.len() - 1])?;
+        for chunk in chunks.iter().rev().skip(1) {
+            write!(f, "{:09}", chunk)?;
+        }
+        Ok(())
+    }
+}
+
+/// The same decimal digits as `Display`, like the built in integers, so
+/// answers print the same whichever type holds them.
+impl Debug for BigUint {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        Display::fmt(self, f)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_from_and_to_u64() {
+        for n in [0, 1, u32::MAX as u64, u32::MAX as u64 + 1, u64::MAX] {
+            assert_eq!(Some(n), BigUint::from(n).to_u64());
+        }
+        assert!(BigUint::from(0).is_zero());
+    }
+
+    #[test]
+    fn test_rem_u64() {
+        assert_eq!(0, BigUint::zero().rem_u64(7));
+        assert_eq!(u64::MAX % 1_000_000_007, BigUint::from(u64::MAX).rem_u64(1_000_000_007));
+
+        // 2^64 * 2^64 = 2^128, and 2^128 mod (2^64 - 1) is 1.
+        let big = &BigUint::from(u64::MAX) + &BigUint::one();
+        assert_eq!(1, (&big * &big).rem_u64(u64::MAX));
+    }
+
+    #[test]
+    fn test_add_carries_into_new_limb() {
+        let sum = BigUint::from(u64::MAX) + BigUint::from(1);
+        assert_eq!(None, sum.to_u64());
+        assert_eq!("18446744073709551616", sum.to_string());
+    }
+
+    #[test]
+    fn test_mul() {
+        let n = BigUint::from(u64::MAX);
+        assert_eq!("340282366920938463426481119284349108225", (&n * &n).to_string());
+        assert_eq!(BigUint::zero(), &n * &BigUint::zero());
+    }
+
+    #[test]
+    fn test_factorial() {
+        let factorial = (1..=30).map(BigUint::from).product::<BigUint>();
+        assert_eq!("265252859812191058636308480000000", factorial.to_string());
+    }
+
+    #[test]
+    fn test_ordering() {
+        let big = BigUint::from(u64::MAX) + BigUint::from(1);
+        assert!(BigUint::from(u64::MAX) < big);
+        assert!(BigUint::from(3) > BigUint::from(2));
+        assert_eq!(Some(&BigUint::from(7)), [BigUint::from(7), BigUint::from(5)].iter().max());
+    }
+
+    #[test]
+    fn test_display_pads_inner_chunks() {
+        let n = BigUint::from(1_000_000_000) * BigUint::from(1_000_000_000) + BigUint::from(7);
+        assert_eq!("1000000000000000007", n.to_string());
+        assert_eq!("1000000000000000007", format!("{:?}", n));
+    }
+}
@@ -0,0 +1,171 @@
+use crate::bigint::BigUint;
+
+/// The numbers a count is kept in, for counts that can outgrow a `u64`.
+pub trait Ring {
+    type Value: Clone;
+
+    fn value(&self, n: u64) -> Self::Value;
+    fn is_zero(&self, a: &Self::Value) -> bool;
+    fn add(&self, a: &Self::Value, b: &Self::Value) -> Self::Value;
+    fn mul(&self, a: &Self::Value, b: &Self::Value) -> Self::Value;
+
+    fn sum<'a>(&self, values: impl IntoIterator<Item = &'a Self::Value>) -> Self::Value where Self::Value: 'a {
+        values.into_iter().fold(self.value(0), |sum, v| self.add(&sum, v))
+    }
+}
+
+/// Exact counts. Exponential growth makes these huge, so this suits step
+/// counts in the thousands, not the trillions.
+pub struct Exact;
+
+impl Ring for Exact {
+    type Value = BigUint;
+
+    fn value(&self, n: u64) -> BigUint {
+        BigUint::from(n)
+    }
+
+    fn is_zero(&self, a: &BigUint) -> bool {
+        a.is_zero()
+    }
+
+    fn add(&self, a: &BigUint, b: &BigUint) -> BigUint {
+        a + b
+    }
+
+    fn mul(&self, a: &BigUint, b: &BigUint) -> BigUint {
+        a * b
+    }
+}
+
+/// Counts modulo a prime, which stay small however far they go.
+pub struct Modulo(u64);
+
+impl Modulo {
+    /// `None` unless `p` is at least 2.
+    pub fn new(p: u64) -> Option<Self> {
+        (p >= 2).then_some(Modulo(p))
+    }
+}
+
+impl Ring for Modulo {
+    type Value = u64;
+
+    fn value(&self, n: u64) -> u64 {
+        n % self.0
+    }
+
+    fn is_zero(&self, a: &u64) -> bool {
+        *a == 0
+    }
+
+    fn add(&self, a: &u64, b: &u64) -> u64 {
+        ((*a as u128 + *b as u128) % self.0 as u128) as u64
+    }
+
+    fn mul(&self, a: &u64, b: &u64) -> u64 {
+        (*a as u128 * *b as u128 % self.0 as u128) as u64
+    }
+}
+
+/// A square matrix over a `Ring`, row-major.
+pub struct Matrix<V> {
+    size: usize,
+    cells: Vec<V>
+}
+
+impl<V: Clone> Matrix<V> {
+    pub fn zero<R: Ring<Value = V>>(size: usize, ring: &R) -> Self {
+        Matrix { size, cells: vec![ring.value(0); size * size] }
+    }
+
+    pub fn add_to<R: Ring<Value = V>>(&mut self, row: usize, col: usize, n: u64, ring: &R) {
+        let cell = &mut self.cells[row * self.size + col];
+        *cell = ring.add(cell, &ring.value(n));
+    }
+
+    pub fn mul<R: Ring<Value = V>>(&self, other: &Matrix<V>, ring: &R) -> Matrix<V> {
+        let n = self.size;
+        let mut cells = vec![ring.value(0); n * n];
+        for i in 0..n {
+            for k in 0..n {
+                let a = &self.cells[i * n + k];
+                if ring.is_zero(a) {
+                    continue;
+                }
+                for j in 0..n {
+                    let b = &other.cells[k * n + j];
+                    if !ring.is_zero(b) {
+                        cells[i * n + j] = ring.add(&cells[i * n + j], &ring.mul(a, b));
+                    }
+                }
+            }
+        }
+        Matrix { size: n, cells }
+    }
+
+    pub fn apply<R: Ring<Value = V>>(&self, vector: &[V], ring: &R) -> Vec<V> {
+        let n = self.size;
+        (0..n).map(|i| {
+            (0..n).fold(ring.value(0), |sum, j| ring.add(&sum, &ring.mul(&self.cells[i * n + j], &vector[j])))
+        }).collect()
+    }
+
+    /// This matrix to the `n`th power applied to `vector`, by repeated
+    /// squaring, so the cost grows with the number of bits in `n`.
+    pub fn pow_apply<R: Ring<Value = V>>(&self, n: u64, vector: Vec<V>, ring: &R) -> Vec<V> {
+        let mut vector = vector;
+        let mut power = Matrix { size: self.size, cells: self.cells.clone() };
+        let mut n = n;
+        while n > 0 {
+            if n & 1 == 1 {
+                vector = power.apply(&vector, ring);
+            }
+            n >>= 1;
+            if n > 0 {
+                power = power.mul(&power, ring);
+            }
+        }
+        vector
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn fibonacci<R: Ring>(n: u64, ring: &R) -> R::Value {
+        let mut step = Matrix::zero(2, ring);
+        step.add_to(0, 1, 1, ring);
+        step.add_to(1, 0, 1, ring);
+        step.add_to(1, 1, 1, ring);
+        step.pow_apply(n, vec![ring.value(0), ring.value(1)], ring)[0].clone()
+    }
+
+    #[test]
+    fn test_pow_apply() {
+        assert_eq!(BigUint::zero(), fibonacci(0, &Exact));
+        assert_eq!(BigUint::from(55), fibonacci(10, &Exact));
+        assert_eq!("354224848179261915075", fibonacci(100, &Exact).to_string());
+    }
+
+    #[test]
+    fn test_modulo_agrees_with_exact() {
+        let p = 1_000_000_007;
+        assert_eq!(fibonacci(500, &Exact).rem_u64(p), fibonacci(500, &Modulo::new(p).unwrap()));
+    }
+
+    #[test]
+    fn test_modulo_arithmetic() {
+        let ring = Modulo::new(u64::MAX).unwrap();
+        assert_eq!(1, ring.add(&(u64::MAX - 1), &2));
+        assert_eq!(1, ring.mul(&(u64::MAX - 1), &(u64::MAX - 1)));
+        assert_eq!(3, ring.sum(&[1, 2, u64::MAX]));
+    }
+
+    #[test]
+    fn test_modulo_needs_a_modulus() {
+        assert!(Modulo::new(0).is_none());
+        assert!(Modulo::new(1).is_none());
+    }
+}
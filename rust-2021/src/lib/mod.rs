@@ -1,3 +1,8 @@
+pub mod bigint;
+pub mod matrix;
+pub mod parallel;
+pub mod rng;
+
 use std::error::Error;
 use std::fmt::Debug;
 use std::fs::File;
@@ -0,0 +1,62 @@
+use std::num::NonZeroUsize;
+use std::thread;
+
+/// The number of threads to use when the caller has no preference.
+pub fn default_threads() -> usize {
+    thread::available_parallelism().map_or(1, NonZeroUsize::get)
+}
+
+/// Calls `f` on every ordered pair `(i, j)` of distinct indices below `n`,
+/// spread over up to `threads` scoped threads. The results come back in
+/// row-major order whatever the thread count.
+pub fn map_pairs<T, F>(n: usize, threads: usize, f: F) -> Vec<((usize, usize), T)>
+    where T: Send, F: Fn(usize, usize) -> T + Sync
+{
+    let pairs = (0..n)
+        .flat_map(|i| (0..n).filter(move |j| i != *j).map(move |j| (i, j)))
+        .collect::<Vec<_>>();
+    if pairs.is_empty() {
+        return vec![];
+    }
+
+    let chunk_size = pairs.len().div_ceil(threads.max(1));
+    let f = &f;
+    thread::scope(|scope| {
+        let handles = pairs.chunks(chunk_size)
+            .map(|chunk| scope.spawn(move || {
+                chunk.iter().map(|&(i, j)| ((i, j), f(i, j))).collect::<Vec<_>>()
+            }))
+            .collect::<Vec<_>>();
+
+        handles.into_iter()
+            .flat_map(|handle| handle.join().expect("pair worker panicked"))
+            .collect()
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_map_pairs_visits_each_ordered_pair_once() {
+        let results = map_pairs(3, 2, |i, j| i * 10 + j);
+        assert_eq!(vec![
+            ((0, 1), 1), ((0, 2), 2), ((1, 0), 10), ((1, 2), 12), ((2, 0), 20), ((2, 1), 21)
+        ], results);
+    }
+
+    #[test]
+    fn test_map_pairs_is_independent_of_thread_count() {
+        let expected = map_pairs(17, 1, |i, j| i * j + i);
+        for threads in [0, 2, 3, 8, 1000] {
+            assert_eq!(expected, map_pairs(17, threads, |i, j| i * j + i));
+        }
+    }
+
+    #[test]
+    fn test_map_pairs_with_fewer_than_two_items() {
+        assert!(map_pairs(0, 4, |i, j| (i, j)).is_empty());
+        assert!(map_pairs(1, 4, |i, j| (i, j)).is_empty());
+    }
+}
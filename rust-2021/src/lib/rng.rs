@@ -0,0 +1,57 @@
+/// xorshift64: small and seedable, so randomised tests and generated inputs
+/// come out the same on every run. Not for anything that needs to be
+/// unpredictable.
+pub struct Rng(u64);
+
+impl Rng {
+    /// The seed can't be zero, so zero is swapped for a fixed constant.
+    pub fn new(seed: u64) -> Self {
+        Rng(if seed == 0 { 0x9e37_79b9_7f4a_7c15 } else { seed })
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// A number below `bound`, which must be positive.
+    pub fn below(&mut self, bound: usize) -> usize {
+        (self.next_u64() % bound as u64) as usize
+    }
+
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.below(i + 1));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_same_seed_same_numbers() {
+        let numbers = |seed| {
+            let mut rng = Rng::new(seed);
+            (0..5).map(|_| rng.next_u64()).collect::<Vec<_>>()
+        };
+        assert_eq!(numbers(7), numbers(7));
+        assert_ne!(numbers(7), numbers(8));
+        assert!(numbers(0).iter().all(|n| *n != 0));
+    }
+
+    #[test]
+    fn test_below_and_shuffle() {
+        let mut rng = Rng::new(2021);
+        assert!((0..1000).all(|_| rng.below(6) < 6));
+
+        let mut items = (0..20).collect::<Vec<_>>();
+        rng.shuffle(&mut items);
+        assert_ne!((0..20).collect::<Vec<_>>(), items);
+        items.sort();
+        assert_eq!((0..20).collect::<Vec<_>>(), items);
+    }
+}